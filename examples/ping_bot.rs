@@ -1,24 +1,33 @@
 use log::{debug, warn};
 use serde_json::json;
-use smalld::SmallD;
+use smalld::{Error, SmallDBuilder};
 
 fn main() {
     pretty_env_logger::init();
 
-    let smalld = SmallD::new().expect("Failed to initialize smalld");
+    let smalld = SmallDBuilder::new()
+        .on_listener_error(|err, event_name, payload| {
+            warn!("{} failed: {}", event_name.unwrap_or("payload"), err);
+            debug!("Failed payload: {:?}", payload);
+        })
+        .build()
+        .expect("Failed to initialize smalld");
 
-    smalld.on_event("MESSAGE_CREATE", move |smalld, json| {
+    smalld.try_on_event("MESSAGE_CREATE", move |smalld, json| {
         if let Some("++ping") = json.get("content").and_then(|c| c.as_str()) {
             debug!("Pong!");
-            if let Some(channel_id) = json.get("channel_id").and_then(|c| c.as_str()) {
-                if let Err(err) = smalld.post(
-                    format!("/channels/{}/messages", channel_id),
-                    json!({"content":"pong"}),
-                ) {
-                    warn!("Error sending pong response: {}", err);
-                }
-            }
+            let channel_id = json
+                .get("channel_id")
+                .and_then(|c| c.as_str())
+                .ok_or_else(|| Error::illegal_state("Message has no channel_id"))?;
+
+            smalld.post(
+                format!("/channels/{}/messages", channel_id),
+                json!({"content":"pong"}),
+            )?;
         }
+
+        Ok(())
     });
 
     smalld.run();
@@ -0,0 +1,518 @@
+use crate::error::Error;
+use crate::http::QueryParameters;
+use crate::payload::{Op, Payload};
+use crate::smalld::SmallD;
+use crate::snowflake::Snowflake;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// An [application command](https://discord.com/developers/docs/interactions/application-commands)
+/// to be registered via [`SmallD::sync_commands`](SmallD#method.sync_commands).
+///
+/// ```rust
+/// use smalld::{ApplicationCommand, CommandChoice, CommandOption, CommandOptionType};
+///
+/// let command = ApplicationCommand::chat_input("colour", "Pick a colour")
+///   .description_localization("de", "Wähle eine Farbe")
+///   .option(
+///     CommandOption::new(CommandOptionType::String, "colour", "The colour")
+///       .required(true)
+///       .choice(("Red", "red"))
+///       .choice(CommandChoice::new("Blue", "blue").name_localization("de", "Blau")),
+///   )
+///   .default_member_permissions(0x20);
+///
+/// assert_eq!(command.to_json()["options"][0]["choices"][1]["value"], "blue");
+/// ```
+#[derive(Clone, Debug, Serialize)]
+pub struct ApplicationCommand {
+    name: String,
+    #[serde(rename = "type")]
+    kind: u8,
+    description: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    options: Vec<CommandOption>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    name_localizations: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    description_localizations: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default_member_permissions: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dm_permission: Option<bool>,
+}
+
+impl ApplicationCommand {
+    fn new<A: Into<String>, B: Into<String>>(kind: u8, name: A, description: B) -> Self {
+        ApplicationCommand {
+            name: name.into(),
+            kind,
+            description: description.into(),
+            options: Vec::new(),
+            name_localizations: BTreeMap::new(),
+            description_localizations: BTreeMap::new(),
+            default_member_permissions: None,
+            dm_permission: None,
+        }
+    }
+
+    /// A slash command.
+    pub fn chat_input<A: Into<String>, B: Into<String>>(name: A, description: B) -> Self {
+        Self::new(1, name, description)
+    }
+
+    /// A command shown in the context menu of a user.
+    pub fn user<S: Into<String>>(name: S) -> Self {
+        Self::new(2, name, "")
+    }
+
+    /// A command shown in the context menu of a message.
+    pub fn message<S: Into<String>>(name: S) -> Self {
+        Self::new(3, name, "")
+    }
+
+    pub fn option(mut self, option: CommandOption) -> Self {
+        self.options.push(option);
+        self
+    }
+
+    pub fn name_localization<A, B>(mut self, locale: A, name: B) -> Self
+    where
+        A: Into<String>,
+        B: Into<String>,
+    {
+        self.name_localizations.insert(locale.into(), name.into());
+        self
+    }
+
+    pub fn description_localization<A, B>(mut self, locale: A, description: B) -> Self
+    where
+        A: Into<String>,
+        B: Into<String>,
+    {
+        self.description_localizations
+            .insert(locale.into(), description.into());
+        self
+    }
+
+    /// Sets the [permissions](https://discord.com/developers/docs/topics/permissions) required
+    /// by default to use the command. `0` allows only administrators.
+    pub fn default_member_permissions(mut self, permissions: u64) -> Self {
+        self.default_member_permissions = Some(permissions.to_string());
+        self
+    }
+
+    /// Sets whether the command is available in DMs. Only applies to global commands.
+    pub fn dm_permission(mut self, dm_permission: bool) -> Self {
+        self.dm_permission = Some(dm_permission);
+        self
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+}
+
+/// The [type](https://discord.com/developers/docs/interactions/application-commands#application-command-object-application-command-option-type)
+/// of a [`CommandOption`](CommandOption).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandOptionType {
+    SubCommand,
+    SubCommandGroup,
+    String,
+    Integer,
+    Boolean,
+    User,
+    Channel,
+    Role,
+    Mentionable,
+    Number,
+    Attachment,
+}
+
+impl From<CommandOptionType> for u8 {
+    fn from(kind: CommandOptionType) -> u8 {
+        match kind {
+            CommandOptionType::SubCommand => 1,
+            CommandOptionType::SubCommandGroup => 2,
+            CommandOptionType::String => 3,
+            CommandOptionType::Integer => 4,
+            CommandOptionType::Boolean => 5,
+            CommandOptionType::User => 6,
+            CommandOptionType::Channel => 7,
+            CommandOptionType::Role => 8,
+            CommandOptionType::Mentionable => 9,
+            CommandOptionType::Number => 10,
+            CommandOptionType::Attachment => 11,
+        }
+    }
+}
+
+/// An option, or subcommand, of an [`ApplicationCommand`](ApplicationCommand).
+#[derive(Clone, Debug, Serialize)]
+pub struct CommandOption {
+    #[serde(rename = "type")]
+    kind: u8,
+    name: String,
+    description: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    required: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    choices: Vec<CommandChoice>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    options: Vec<CommandOption>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    name_localizations: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    description_localizations: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    autocomplete: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_value: Option<Value>,
+}
+
+impl CommandOption {
+    pub fn new<A: Into<String>, B: Into<String>>(
+        kind: CommandOptionType,
+        name: A,
+        description: B,
+    ) -> Self {
+        CommandOption {
+            kind: kind.into(),
+            name: name.into(),
+            description: description.into(),
+            required: false,
+            choices: Vec::new(),
+            options: Vec::new(),
+            name_localizations: BTreeMap::new(),
+            description_localizations: BTreeMap::new(),
+            autocomplete: false,
+            min_value: None,
+            max_value: None,
+        }
+    }
+
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    pub fn choice<C: Into<CommandChoice>>(mut self, choice: C) -> Self {
+        self.choices.push(choice.into());
+        self
+    }
+
+    /// Adds an option to a subcommand or subcommand group.
+    pub fn option(mut self, option: CommandOption) -> Self {
+        self.options.push(option);
+        self
+    }
+
+    pub fn name_localization<A, B>(mut self, locale: A, name: B) -> Self
+    where
+        A: Into<String>,
+        B: Into<String>,
+    {
+        self.name_localizations.insert(locale.into(), name.into());
+        self
+    }
+
+    pub fn description_localization<A, B>(mut self, locale: A, description: B) -> Self
+    where
+        A: Into<String>,
+        B: Into<String>,
+    {
+        self.description_localizations
+            .insert(locale.into(), description.into());
+        self
+    }
+
+    /// Sets whether choices are provided by responding to autocomplete interactions, via
+    /// [`Interaction::autocomplete`](crate::Interaction#method.autocomplete).
+    pub fn autocomplete(mut self, autocomplete: bool) -> Self {
+        self.autocomplete = autocomplete;
+        self
+    }
+
+    /// Sets the minimum value of an integer or number option.
+    pub fn min_value<V: Into<Value>>(mut self, min_value: V) -> Self {
+        self.min_value = Some(min_value.into());
+        self
+    }
+
+    /// Sets the maximum value of an integer or number option.
+    pub fn max_value<V: Into<Value>>(mut self, max_value: V) -> Self {
+        self.max_value = Some(max_value.into());
+        self
+    }
+}
+
+/// A choice for a [`CommandOption`](CommandOption). Can also be created from a tuple of name and
+/// value.
+#[derive(Clone, Debug, Serialize)]
+pub struct CommandChoice {
+    name: String,
+    value: Value,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    name_localizations: BTreeMap<String, String>,
+}
+
+impl CommandChoice {
+    pub fn new<N: Into<String>, V: Into<Value>>(name: N, value: V) -> Self {
+        CommandChoice {
+            name: name.into(),
+            value: value.into(),
+            name_localizations: BTreeMap::new(),
+        }
+    }
+
+    pub fn name_localization<A, B>(mut self, locale: A, name: B) -> Self
+    where
+        A: Into<String>,
+        B: Into<String>,
+    {
+        self.name_localizations.insert(locale.into(), name.into());
+        self
+    }
+}
+
+impl<N: Into<String>, V: Into<Value>> From<(N, V)> for CommandChoice {
+    fn from((name, value): (N, V)) -> Self {
+        CommandChoice::new(name, value)
+    }
+}
+
+/// Where commands passed to [`SmallD::sync_commands`](SmallD#method.sync_commands) are
+/// registered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandScope {
+    /// Available in all guilds the application is in, and in DMs.
+    Global,
+
+    /// Available only in the given guild. Changes to guild commands apply immediately, so are
+    /// useful during development.
+    Guild(Snowflake),
+}
+
+/// The application id of the current application, as received in the READY event.
+#[derive(Clone, Default)]
+pub(crate) struct ApplicationId(Arc<Mutex<Option<Snowflake>>>);
+
+impl ApplicationId {
+    pub fn attach(&self, smalld: &SmallD) {
+        let id = self.clone();
+
+        smalld.on_internal_payload(move |_, p| match p {
+            Payload {
+                op: Op::Dispatch,
+                t: Some(event_name),
+                d: Some(d),
+                ..
+            } if event_name == "READY" => {
+                if let Some(application_id) = d
+                    .pointer("/application/id")
+                    .and_then(Value::as_str)
+                    .and_then(|s| s.parse().ok())
+                {
+                    id.set(application_id);
+                }
+            }
+            _ => (),
+        });
+    }
+
+    pub fn get(&self) -> Option<Snowflake> {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn set(&self, id: Snowflake) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Some(id);
+    }
+}
+
+/// Fields of commands, options, and choices that are compared when syncing commands. Any other
+/// fields returned by Discord, such as `id` and `version`, are ignored.
+const COMPARED_FIELDS: [&str; 14] = [
+    "name",
+    "type",
+    "description",
+    "options",
+    "choices",
+    "value",
+    "required",
+    "name_localizations",
+    "description_localizations",
+    "default_member_permissions",
+    "dm_permission",
+    "autocomplete",
+    "min_value",
+    "max_value",
+];
+
+/// Normalizes a command, option, or choice for comparison, by removing fields that are not
+/// compared and those that are empty or at their default. Nested options and choices are
+/// normalized in turn, while other fields, such as localization maps, are compared as they are.
+fn normalize(value: &Value) -> Value {
+    let map = match value {
+        Value::Object(map) => map,
+        _ => return value.clone(),
+    };
+
+    Value::Object(
+        map.iter()
+            .filter(|(k, _)| COMPARED_FIELDS.contains(&k.as_str()))
+            .filter(|(k, v)| !is_default(k, v))
+            .map(|(k, v)| {
+                let v = match (k.as_str(), v) {
+                    ("options", Value::Array(a)) | ("choices", Value::Array(a)) => {
+                        Value::Array(a.iter().map(normalize).collect())
+                    }
+                    _ => normalize_number(v),
+                };
+                (k.clone(), v)
+            })
+            .collect::<Map<_, _>>(),
+    )
+}
+
+/// Converts numbers to floats, as Discord may return `1.0` for a `min_value` sent as `1`.
+fn normalize_number(value: &Value) -> Value {
+    match value {
+        Value::Number(n) => n
+            .as_f64()
+            .and_then(serde_json::Number::from_f64)
+            .map_or_else(|| value.clone(), Value::Number),
+        _ => value.clone(),
+    }
+}
+
+fn is_default(key: &str, value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Bool(b) => *b == (key == "dm_permission"),
+        Value::String(s) => s.is_empty() && key != "value",
+        Value::Array(a) => a.is_empty(),
+        Value::Object(o) => o.is_empty(),
+        Value::Number(_) => false,
+    }
+}
+
+fn sorted(commands: &[Value]) -> Vec<Value> {
+    let mut commands: Vec<Value> = commands.iter().map(normalize).collect();
+    commands.sort_by_key(|c| (c["type"].as_u64(), c["name"].as_str().map(str::to_string)));
+    commands
+}
+
+pub(crate) fn sync_commands(
+    smalld: &SmallD,
+    scope: CommandScope,
+    commands: &[ApplicationCommand],
+) -> Result<bool, Error> {
+    let application_id = smalld.application_id()?;
+
+    let path = match scope {
+        CommandScope::Global => format!("/applications/{}/commands", application_id),
+        CommandScope::Guild(guild_id) => format!(
+            "/applications/{}/guilds/{}/commands",
+            application_id, guild_id
+        ),
+    };
+
+    let desired: Vec<Value> = commands.iter().map(ApplicationCommand::to_json).collect();
+
+    // Without this Discord leaves out localizations, so localized commands would never match
+    let parameters = QueryParameters::new().add("with_localizations", "true");
+
+    let existing = match smalld.get_with_parameters(&path, parameters)? {
+        Value::Array(existing) => existing,
+        _ => return Err(Error::illegal_state("Expected array of commands")),
+    };
+
+    if sorted(&desired) == sorted(&existing) {
+        return Ok(false);
+    }
+
+    smalld.put(&path, Value::Array(desired))?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_ignores_fields_not_compared() {
+        let desired = ApplicationCommand::chat_input("ping", "Pings").to_json();
+        let existing = json!({
+            "id": "1",
+            "version": "2",
+            "type": 1,
+            "name": "ping",
+            "description": "Pings",
+            "dm_permission": true,
+            "options": [],
+        });
+
+        assert_eq!(sorted(&[desired]), sorted(&[existing]));
+    }
+
+    #[test]
+    fn it_compares_localizations() {
+        let command = |description: &str| {
+            ApplicationCommand::chat_input("count", "Counts")
+                .description_localization("de", "Zählt")
+                .option(
+                    CommandOption::new(CommandOptionType::Integer, "start", "Start")
+                        .description_localization("de", description),
+                )
+                .to_json()
+        };
+
+        assert_eq!(sorted(&[command("Eins")]), sorted(&[command("Eins")]));
+        assert_ne!(sorted(&[command("Eins")]), sorted(&[command("Zwei")]));
+    }
+
+    #[test]
+    fn it_matches_command_as_returned_by_discord() {
+        let desired = ApplicationCommand::chat_input("count", "Counts")
+            .name_localization("de", "zählen")
+            .option(
+                CommandOption::new(CommandOptionType::Integer, "start", "Start")
+                    .description_localization("de", "Anfang")
+                    .min_value(1)
+                    .choice(("one", 1)),
+            )
+            .to_json();
+
+        let existing = json!({
+            "id": "1049823651789627452",
+            "application_id": "1049823550178951188",
+            "version": "1049823651789627453",
+            "default_member_permissions": null,
+            "type": 1,
+            "name": "count",
+            "name_localizations": {"de": "zählen"},
+            "description": "Counts",
+            "description_localizations": null,
+            "dm_permission": true,
+            "nsfw": false,
+            "options": [{
+                "type": 4,
+                "name": "start",
+                "name_localizations": null,
+                "description": "Start",
+                "description_localizations": {"de": "Anfang"},
+                "min_value": 1.0,
+                "choices": [{"name": "one", "name_localizations": null, "value": 1}],
+            }],
+        });
+
+        assert_eq!(sorted(&[desired]), sorted(&[existing]));
+    }
+}
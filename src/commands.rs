@@ -0,0 +1,589 @@
+use crate::error::Error;
+use crate::interaction::Interaction;
+use crate::smalld::SmallD;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+type Handler = dyn Fn(&CommandContext) -> Result<(), Error> + Send + Sync + 'static;
+type Check = dyn Fn(&CommandContext) -> bool + Send + Sync + 'static;
+type RejectionHandler = dyn Fn(&CommandContext, Rejection) -> Result<(), Error> + Send + Sync;
+
+/// A command handled by a [`CommandRouter`](CommandRouter).
+pub struct Command {
+    name: String,
+    handler: Box<Handler>,
+    cooldown: Option<Duration>,
+    checks: Vec<Box<Check>>,
+}
+
+impl Command {
+    pub fn new<S, F>(name: S, handler: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&CommandContext) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        Command {
+            name: name.into(),
+            handler: Box::new(handler),
+            cooldown: None,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Sets how long a user must wait between uses of the command.
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = Some(cooldown);
+        self
+    }
+
+    /// Adds a check that must pass for the command to be run, such as whether the user has a
+    /// role or [permission](CommandContext#method.member_permissions).
+    pub fn check<F>(mut self, check: F) -> Self
+    where
+        F: Fn(&CommandContext) -> bool + Send + Sync + 'static,
+    {
+        self.checks.push(Box::new(check));
+        self
+    }
+}
+
+/// Why a command was not run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    /// The user used the command too recently, and must wait for the remaining duration.
+    Cooldown(Duration),
+
+    /// One of the command's checks did not pass.
+    CheckFailed,
+}
+
+/// Routes messages starting with a prefix, and slash commands, to [`Command`s](Command) by
+/// name. Available with the `commands` feature.
+///
+/// For messages the text after the command name is split on whitespace into arguments, with
+/// double quotes grouping words into a single argument. For slash commands the arguments are
+/// the values of the options given. Either way they are available as [`Args`](Args).
+///
+/// ```no_run
+/// use smalld::{Command, CommandRouter, SmallD};
+/// use std::time::Duration;
+///
+/// let smalld = SmallD::new().expect("Failed to initialize smalld");
+///
+/// CommandRouter::new()
+///   .prefix("++")
+///   .mention_prefix(true)
+///   .command(Command::new("ping", |ctx| ctx.reply("pong")))
+///   .command(
+///     Command::new("echo", |ctx| ctx.reply(ctx.args().rest()))
+///       .cooldown(Duration::from_secs(5))
+///       .check(|ctx| ctx.guild_id().is_some()),
+///   )
+///   .attach(&smalld);
+///
+/// smalld.run();
+/// ```
+#[derive(Clone)]
+pub struct CommandRouter {
+    prefixes: Vec<String>,
+    mention_prefix: bool,
+    commands: Arc<HashMap<String, Arc<Command>>>,
+    rejection_handler: Option<Arc<RejectionHandler>>,
+    /// When each user's cooldown for each command ends, keyed by command name and user id.
+    cooldowns: Arc<Mutex<HashMap<(String, String), Instant>>>,
+    bot_user_id: Arc<RwLock<Option<String>>>,
+}
+
+impl CommandRouter {
+    pub fn new() -> Self {
+        CommandRouter {
+            prefixes: Vec::new(),
+            mention_prefix: false,
+            commands: Arc::new(HashMap::new()),
+            rejection_handler: None,
+            cooldowns: Arc::new(Mutex::new(HashMap::new())),
+            bot_user_id: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Adds a prefix for commands in messages. Multiple prefixes may be added.
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    /// Sets whether mentioning the bot can be used as a prefix, e.g. `@Bot ping`.
+    pub fn mention_prefix(mut self, mention_prefix: bool) -> Self {
+        self.mention_prefix = mention_prefix;
+        self
+    }
+
+    pub fn command(mut self, command: Command) -> Self {
+        Arc::make_mut(&mut self.commands).insert(command.name.to_lowercase(), Arc::new(command));
+        self
+    }
+
+    /// Sets a handler to be called when a command is not run due to a cooldown or failed check.
+    /// By default slash commands are responded to with an ephemeral message explaining why, and
+    /// messages are ignored.
+    pub fn on_rejection<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&CommandContext, Rejection) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        self.rejection_handler = Some(Arc::new(f));
+        self
+    }
+
+    /// Registers listeners with `smalld` for messages and interactions to be routed.
+    pub fn attach(self, smalld: &SmallD) {
+        let router = self.clone();
+        smalld.on_event("READY", move |_, d| {
+            if let Some(id) = d.pointer("/user/id").and_then(Value::as_str) {
+                *router
+                    .bot_user_id
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner) = Some(id.to_string());
+            }
+        });
+
+        let router = self.clone();
+        smalld.try_on_event("MESSAGE_CREATE", move |s, d| router.on_message(s, d));
+
+        smalld.try_on_interaction(move |i| self.on_interaction(i));
+    }
+
+    fn on_message(&self, smalld: &SmallD, message: &Value) -> Result<(), Error> {
+        if message.pointer("/author/bot").and_then(Value::as_bool) == Some(true) {
+            return Ok(());
+        }
+
+        let content = message["content"].as_str().unwrap_or_default();
+
+        let rest = match self.strip_prefix(content) {
+            Some(rest) => rest.trim_start(),
+            None => return Ok(()),
+        };
+
+        let (name, rest) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+
+        let context = CommandContext {
+            smalld: smalld.clone(),
+            name: name.to_string(),
+            args: Args::parse(rest.trim()),
+            source: Source::Message(message.clone()),
+        };
+
+        self.run(&context)
+    }
+
+    fn on_interaction(&self, interaction: &Interaction) -> Result<(), Error> {
+        // Only application commands, not components, autocomplete, or modals
+        if interaction.kind() != Some(2) {
+            return Ok(());
+        }
+
+        let name = interaction.command_name().unwrap_or_default().to_string();
+        let args = Args::from_options(interaction.data().pointer("/data/options"));
+
+        let context = CommandContext {
+            smalld: interaction.smalld().clone(),
+            name,
+            args,
+            source: Source::Interaction(Box::new(interaction.clone())),
+        };
+
+        self.run(&context)
+    }
+
+    fn strip_prefix<'a>(&self, content: &'a str) -> Option<&'a str> {
+        if let Some(rest) = self
+            .prefixes
+            .iter()
+            .find_map(|prefix| content.strip_prefix(prefix.as_str()))
+        {
+            return Some(rest);
+        }
+
+        if !self.mention_prefix {
+            return None;
+        }
+
+        let bot_user_id = self
+            .bot_user_id
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let id = bot_user_id.as_deref()?;
+
+        content
+            .strip_prefix(&format!("<@{}>", id))
+            .or_else(|| content.strip_prefix(&format!("<@!{}>", id)))
+    }
+
+    fn run(&self, context: &CommandContext) -> Result<(), Error> {
+        let command = match self.commands.get(&context.name.to_lowercase()) {
+            Some(command) => command,
+            None => return Ok(()),
+        };
+
+        if !command.checks.iter().all(|check| check(context)) {
+            return self.reject(context, Rejection::CheckFailed);
+        }
+
+        if let Some(cooldown) = command.cooldown {
+            if let Some(remaining) = self.cooldown_remaining(&command.name, context, cooldown) {
+                return self.reject(context, Rejection::Cooldown(remaining));
+            }
+        }
+
+        (command.handler)(context)
+    }
+
+    /// Returns how long the user must wait if the command is on cooldown, otherwise records
+    /// this use of the command.
+    fn cooldown_remaining(
+        &self,
+        name: &str,
+        context: &CommandContext,
+        cooldown: Duration,
+    ) -> Option<Duration> {
+        let key = (name.to_string(), context.user_id()?.to_string());
+        let now = Instant::now();
+
+        let mut cooldowns = self
+            .cooldowns
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Forget cooldowns that have ended, so that the map does not grow without bound
+        cooldowns.retain(|_, ends_at| *ends_at > now);
+
+        match cooldowns.get(&key) {
+            Some(ends_at) => Some(*ends_at - now),
+            None => {
+                cooldowns.insert(key, now + cooldown);
+                None
+            }
+        }
+    }
+
+    fn reject(&self, context: &CommandContext, rejection: Rejection) -> Result<(), Error> {
+        if let Some(handler) = &self.rejection_handler {
+            return handler(context, rejection);
+        }
+
+        match (&context.source, rejection) {
+            (Source::Interaction(i), Rejection::Cooldown(remaining)) => i.respond(json!({
+                "content": format!("Try again in {} seconds", remaining.as_secs() + 1),
+                "flags": 64,
+            })),
+            (Source::Interaction(i), Rejection::CheckFailed) => i.respond(json!({
+                "content": "You can't use this command here",
+                "flags": 64,
+            })),
+            (Source::Message(_), _) => Ok(()),
+        }
+    }
+}
+
+impl Default for CommandRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+enum Source {
+    Message(Value),
+    Interaction(Box<Interaction>),
+}
+
+/// The context in which a [`Command`](Command) is run, either a message or a slash command.
+pub struct CommandContext {
+    smalld: SmallD,
+    name: String,
+    args: Args,
+    source: Source,
+}
+
+impl CommandContext {
+    pub fn smalld(&self) -> &SmallD {
+        &self.smalld
+    }
+
+    /// The name of the command, as given by the user.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn args(&self) -> &Args {
+        &self.args
+    }
+
+    /// The message, if the command was from a message.
+    pub fn message(&self) -> Option<&Value> {
+        match &self.source {
+            Source::Message(message) => Some(message),
+            Source::Interaction(_) => None,
+        }
+    }
+
+    /// The interaction, if the command was a slash command.
+    pub fn interaction(&self) -> Option<&Interaction> {
+        match &self.source {
+            Source::Message(_) => None,
+            Source::Interaction(interaction) => Some(interaction),
+        }
+    }
+
+    fn data(&self) -> &Value {
+        match &self.source {
+            Source::Message(message) => message,
+            Source::Interaction(interaction) => interaction.data(),
+        }
+    }
+
+    pub fn user_id(&self) -> Option<&str> {
+        let user = match &self.source {
+            Source::Message(message) => message.get("author"),
+            Source::Interaction(interaction) => interaction.user(),
+        };
+
+        user.and_then(|u| u["id"].as_str())
+    }
+
+    pub fn guild_id(&self) -> Option<&str> {
+        self.data()["guild_id"].as_str()
+    }
+
+    pub fn channel_id(&self) -> Option<&str> {
+        self.data()["channel_id"].as_str()
+    }
+
+    /// The permissions of the member in the channel, if the command was a slash command in a
+    /// guild. For messages this is not known without fetching the member's roles.
+    pub fn member_permissions(&self) -> Option<u64> {
+        self.interaction()?
+            .data()
+            .pointer("/member/permissions")
+            .and_then(Value::as_str)
+            .and_then(|p| p.parse().ok())
+    }
+
+    /// Replies with a message with the given content.
+    pub fn reply<S: Into<String>>(&self, content: S) -> Result<(), Error> {
+        self.reply_with(json!({ "content": content.into() }))
+    }
+
+    /// Replies with the given message. For messages this is sent as a reply to the message,
+    /// and for slash commands as the response to the interaction.
+    pub fn reply_with(&self, mut json: Value) -> Result<(), Error> {
+        match &self.source {
+            Source::Interaction(interaction) => interaction.respond(json),
+            Source::Message(message) => {
+                let channel_id = message["channel_id"]
+                    .as_str()
+                    .ok_or_else(|| Error::illegal_state("Message has no channel_id"))?;
+
+                json["message_reference"] = json!({ "message_id": message["id"] });
+
+                self.smalld
+                    .post(format!("/channels/{}/messages", channel_id), json)?;
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The arguments of a command. For messages these are positional only, while for slash
+/// commands they are also available by option name.
+///
+/// ```rust
+/// use smalld::Args;
+///
+/// let args = Args::parse(r#"ban "Some User" 7"#);
+///
+/// assert_eq!(args.get(1), Some("Some User"));
+/// assert_eq!(args.get_as::<u32>(2), Some(7));
+/// assert_eq!(args.rest(), r#"ban "Some User" 7"#);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Args {
+    raw: String,
+    positional: Vec<String>,
+    named: Vec<(String, Value)>,
+    subcommand: Option<String>,
+}
+
+impl Args {
+    /// Splits `s` on whitespace, treating text in double quotes as a single argument. A
+    /// backslash escapes the character following it.
+    pub fn parse<S: Into<String>>(s: S) -> Args {
+        let raw = s.into();
+        let mut positional = Vec::new();
+        let mut current: Option<String> = None;
+        let mut quoted = false;
+        let mut chars = raw.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        current.get_or_insert_with(String::new).push(escaped);
+                    }
+                }
+                '"' => {
+                    quoted = !quoted;
+                    current.get_or_insert_with(String::new);
+                }
+                c if c.is_whitespace() && !quoted => {
+                    positional.extend(current.take());
+                }
+                c => current.get_or_insert_with(String::new).push(c),
+            }
+        }
+
+        positional.extend(current);
+
+        Args {
+            raw,
+            positional,
+            ..Args::default()
+        }
+    }
+
+    /// Builds the arguments from the options of a slash command, descending into any
+    /// subcommands.
+    fn from_options(options: Option<&Value>) -> Args {
+        let mut args = Args::default();
+        let mut options = options.and_then(Value::as_array);
+        let mut subcommands = Vec::new();
+
+        while let Some(opts) = options {
+            match opts.first() {
+                Some(o)
+                    if o.get("options").is_some()
+                        || matches!(o["type"].as_u64(), Some(1) | Some(2)) =>
+                {
+                    subcommands.extend(o["name"].as_str().map(str::to_string));
+                    options = o.get("options").and_then(Value::as_array);
+                }
+                _ => {
+                    for option in opts {
+                        let name = option["name"].as_str().unwrap_or_default().to_string();
+                        let value = option["value"].clone();
+
+                        args.positional.push(match &value {
+                            Value::String(s) => s.clone(),
+                            v => v.to_string(),
+                        });
+                        args.named.push((name, value));
+                    }
+                    break;
+                }
+            }
+        }
+
+        args.raw = args.positional.join(" ");
+        args.subcommand = if subcommands.is_empty() {
+            None
+        } else {
+            Some(subcommands.join(" "))
+        };
+
+        args
+    }
+
+    /// The argument at `index`.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.positional.get(index).map(String::as_str)
+    }
+
+    /// Parses the argument at `index`, returning `None` if there is none or it fails to parse.
+    pub fn get_as<T: FromStr>(&self, index: usize) -> Option<T> {
+        self.get(index).and_then(|a| a.parse().ok())
+    }
+
+    /// The value of the option with the given name. Only available for slash commands.
+    pub fn option(&self, name: &str) -> Option<&Value> {
+        self.named.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// The subcommand invoked, if any, with the group and subcommand names separated by a space.
+    /// Only available for slash commands.
+    pub fn subcommand(&self) -> Option<&str> {
+        self.subcommand.as_deref()
+    }
+
+    /// All arguments as they were given, without parsing.
+    pub fn rest(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn len(&self) -> usize {
+        self.positional.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positional.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smalld::SmallDBuilder;
+    use std::thread::sleep;
+
+    fn message(smalld: &SmallD, name: &str, user_id: &str) -> CommandContext {
+        CommandContext {
+            smalld: smalld.clone(),
+            name: name.to_string(),
+            args: Args::default(),
+            source: Source::Message(json!({"id": "1", "author": {"id": user_id}})),
+        }
+    }
+
+    #[test]
+    fn it_keeps_cooldowns_of_commands_with_a_longer_cooldown() {
+        let smalld = SmallDBuilder::new().token("token").build().unwrap();
+        let rejections = Arc::new(Mutex::new(Vec::new()));
+
+        let router = {
+            let rejections = rejections.clone();
+            CommandRouter::new()
+                .command(Command::new("slow", |_| Ok(())).cooldown(Duration::from_secs(60)))
+                .command(Command::new("fast", |_| Ok(())).cooldown(Duration::from_millis(10)))
+                .on_rejection(move |ctx, rejection| {
+                    rejections
+                        .lock()
+                        .unwrap()
+                        .push((ctx.name().to_string(), rejection));
+                    Ok(())
+                })
+        };
+
+        router.run(&message(&smalld, "slow", "a")).unwrap();
+        router.run(&message(&smalld, "fast", "b")).unwrap();
+        sleep(Duration::from_millis(20));
+        router.run(&message(&smalld, "fast", "b")).unwrap();
+        router.run(&message(&smalld, "slow", "a")).unwrap();
+
+        let rejections = rejections.lock().unwrap();
+        assert_eq!(rejections.len(), 1);
+        assert_eq!(rejections[0].0, "slow");
+        assert!(matches!(rejections[0].1, Rejection::Cooldown(d) if d > Duration::from_secs(59)));
+    }
+
+    #[test]
+    fn it_parses_quoted_args() {
+        let args = Args::parse(r#"one "two three" fo\"ur"#);
+
+        assert_eq!(args.get(0), Some("one"));
+        assert_eq!(args.get(1), Some("two three"));
+        assert_eq!(args.get(2), Some("fo\"ur"));
+        assert_eq!(args.len(), 3);
+    }
+}
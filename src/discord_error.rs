@@ -0,0 +1,240 @@
+use serde_json::{Map, Value};
+
+/// [JSON error code](https://discord.com/developers/docs/topics/opcodes-and-status-codes#json-json-error-codes)
+/// as will be received in an [`Error::DiscordApi`](crate::Error::DiscordApi).
+///
+/// Only commonly encountered codes have their own variant, all others are `Unknown`.
+///
+/// ```rust
+/// use smalld::JsonErrorCode;
+/// assert_eq!(JsonErrorCode::from(50013), JsonErrorCode::MissingPermissions);
+/// assert_eq!(JsonErrorCode::from(12345), JsonErrorCode::Unknown(12345));
+/// assert_eq!(u64::from(JsonErrorCode::UnknownMessage), 10008);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JsonErrorCode {
+    General,
+    UnknownChannel,
+    UnknownGuild,
+    UnknownMember,
+    UnknownMessage,
+    UnknownRole,
+    UnknownUser,
+    UnknownEmoji,
+    UnknownWebhook,
+    UnknownBan,
+    UnknownInteraction,
+    MaximumReactions,
+    Unauthorized,
+    InteractionAlreadyAcknowledged,
+    MissingAccess,
+    CannotEditMessageByAnotherUser,
+    CannotSendEmptyMessage,
+    CannotSendMessagesToUser,
+    MissingPermissions,
+    InvalidOAuth2AccessToken,
+    MessageTooOldToBulkDelete,
+    InvalidFormBody,
+    Unknown(u64),
+}
+
+impl From<u64> for JsonErrorCode {
+    fn from(code: u64) -> Self {
+        use JsonErrorCode::*;
+        match code {
+            0 => General,
+            10003 => UnknownChannel,
+            10004 => UnknownGuild,
+            10007 => UnknownMember,
+            10008 => UnknownMessage,
+            10011 => UnknownRole,
+            10013 => UnknownUser,
+            10014 => UnknownEmoji,
+            10015 => UnknownWebhook,
+            10026 => UnknownBan,
+            10062 => UnknownInteraction,
+            30010 => MaximumReactions,
+            40001 => Unauthorized,
+            40060 => InteractionAlreadyAcknowledged,
+            50001 => MissingAccess,
+            50005 => CannotEditMessageByAnotherUser,
+            50006 => CannotSendEmptyMessage,
+            50007 => CannotSendMessagesToUser,
+            50013 => MissingPermissions,
+            50025 => InvalidOAuth2AccessToken,
+            50034 => MessageTooOldToBulkDelete,
+            50035 => InvalidFormBody,
+            n => Unknown(n),
+        }
+    }
+}
+
+impl From<JsonErrorCode> for u64 {
+    fn from(code: JsonErrorCode) -> Self {
+        use JsonErrorCode::*;
+        match code {
+            General => 0,
+            UnknownChannel => 10003,
+            UnknownGuild => 10004,
+            UnknownMember => 10007,
+            UnknownMessage => 10008,
+            UnknownRole => 10011,
+            UnknownUser => 10013,
+            UnknownEmoji => 10014,
+            UnknownWebhook => 10015,
+            UnknownBan => 10026,
+            UnknownInteraction => 10062,
+            MaximumReactions => 30010,
+            Unauthorized => 40001,
+            InteractionAlreadyAcknowledged => 40060,
+            MissingAccess => 50001,
+            CannotEditMessageByAnotherUser => 50005,
+            CannotSendEmptyMessage => 50006,
+            CannotSendMessagesToUser => 50007,
+            MissingPermissions => 50013,
+            InvalidOAuth2AccessToken => 50025,
+            MessageTooOldToBulkDelete => 50034,
+            InvalidFormBody => 50035,
+            Unknown(n) => n,
+        }
+    }
+}
+
+/// An error for a single field of a request, as found in the `errors` of an
+/// [`Error::DiscordApi`](crate::Error::DiscordApi).
+///
+/// The `path` locates the field in the request body, with nested fields and array indices
+/// separated by `.`, e.g. `embeds.0.title`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldError {
+    pub path: String,
+    pub code: String,
+    pub message: String,
+}
+
+/// Flattens the nested `errors` tree of a Discord error response into a list of
+/// [`FieldError`](FieldError)s.
+pub(crate) fn flatten_errors(errors: &Value) -> Vec<FieldError> {
+    let mut flattened = Vec::new();
+
+    if let Some(obj) = errors.as_object() {
+        flatten_into(&mut flattened, "", obj);
+    }
+
+    flattened
+}
+
+fn flatten_into(flattened: &mut Vec<FieldError>, path: &str, obj: &Map<String, Value>) {
+    for (key, value) in obj {
+        if key == "_errors" {
+            let errors = value.as_array().map(Vec::as_slice).unwrap_or_default();
+
+            for e in errors {
+                let field = |name| e.get(name).and_then(Value::as_str).unwrap_or("");
+
+                flattened.push(FieldError {
+                    path: path.to_string(),
+                    code: field("code").to_string(),
+                    message: field("message").to_string(),
+                });
+            }
+        } else if let Some(nested) = value.as_object() {
+            let nested_path = if path.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", path, key)
+            };
+
+            flatten_into(flattened, &nested_path, nested);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn field_error(path: &str, code: &str, message: &str) -> FieldError {
+        FieldError {
+            path: path.to_string(),
+            code: code.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn it_flattens_nested_errors() {
+        let errors = json!({
+            "content": {
+                "_errors": [
+                    { "code": "BASE_TYPE_MAX_LENGTH", "message": "Must be 2000 or fewer in length." },
+                    { "code": "CONTENT_TYPE_INVALID", "message": "Invalid content." }
+                ]
+            },
+            "embeds": {
+                "0": {
+                    "title": {
+                        "_errors": [{ "code": "BASE_TYPE_REQUIRED", "message": "This field is required" }]
+                    }
+                },
+                "2": {
+                    "fields": {
+                        "1": {
+                            "name": {
+                                "_errors": [{ "code": "BASE_TYPE_REQUIRED", "message": "This field is required" }]
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        let mut flattened = flatten_errors(&errors);
+        flattened.sort_by(|a, b| (&a.path, &a.code).cmp(&(&b.path, &b.code)));
+
+        assert_eq!(
+            flattened,
+            vec![
+                field_error(
+                    "content",
+                    "BASE_TYPE_MAX_LENGTH",
+                    "Must be 2000 or fewer in length."
+                ),
+                field_error("content", "CONTENT_TYPE_INVALID", "Invalid content."),
+                field_error(
+                    "embeds.0.title",
+                    "BASE_TYPE_REQUIRED",
+                    "This field is required"
+                ),
+                field_error(
+                    "embeds.2.fields.1.name",
+                    "BASE_TYPE_REQUIRED",
+                    "This field is required"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_flattens_top_level_errors() {
+        let errors = json!({
+            "_errors": [{ "code": "APPLICATION_COMMAND_TOO_LARGE", "message": "Command exceeds maximum size" }]
+        });
+
+        assert_eq!(
+            flatten_errors(&errors),
+            vec![field_error(
+                "",
+                "APPLICATION_COMMAND_TOO_LARGE",
+                "Command exceeds maximum size"
+            )]
+        );
+    }
+
+    #[test]
+    fn it_ignores_errors_that_are_not_an_object() {
+        assert!(flatten_errors(&Value::Null).is_empty());
+        assert!(flatten_errors(&json!([1, 2])).is_empty());
+    }
+}
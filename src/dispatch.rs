@@ -0,0 +1,295 @@
+use crate::payload::Payload;
+use crate::smalld::SmallD;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::spawn;
+
+/// How listeners registered with [`SmallD`](crate::SmallD) are called. Configure via
+/// [`dispatch_mode`](crate::SmallDBuilder#method.dispatch_mode).
+///
+/// SmallD's own listeners, such as those for heartbeating and identifying, are always called on
+/// the thread receiving from the gateway.
+#[derive(Clone, Copy, Debug)]
+pub enum DispatchMode {
+    /// Listeners are called on the thread receiving from the gateway, one payload at a time.
+    /// This is the default.
+    Gateway,
+
+    /// Listeners are called on a pool of `threads` threads, so a slow listener does not delay
+    /// receiving further payloads, nor other listeners handling them. A listener may be called
+    /// for several payloads at once, so any state it mutates must be behind a lock or atomic.
+    ThreadPool {
+        threads: usize,
+        ordering: EventOrdering,
+    },
+}
+
+/// Ordering guarantees for payloads dispatched on a
+/// [`ThreadPool`](DispatchMode::ThreadPool).
+///
+/// The guild of a payload is its `guild_id`, or its `id` for `GUILD_CREATE`, `GUILD_UPDATE`, and
+/// `GUILD_DELETE`. Payloads with neither a guild nor a channel, such as `READY` or those for
+/// direct messages without a `channel_id`, are not ordered with respect to any other payload.
+#[derive(Clone, Copy, Debug)]
+pub enum EventOrdering {
+    /// Payloads may be handled in any order.
+    Unordered,
+
+    /// Payloads for the same guild are handled in the order they were received.
+    PerGuild,
+
+    /// Payloads for the same channel are handled in the order they were received. Payloads
+    /// without a channel are ordered per guild.
+    PerChannel,
+}
+
+/// Events whose data is the guild itself, so that the guild id is its `id`.
+const GUILD_EVENTS: [&str; 3] = ["GUILD_CREATE", "GUILD_UPDATE", "GUILD_DELETE"];
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+pub(crate) enum Dispatcher {
+    Gateway,
+    ThreadPool(ThreadPool),
+}
+
+impl Dispatcher {
+    pub fn new(mode: DispatchMode) -> Dispatcher {
+        match mode {
+            DispatchMode::Gateway => Dispatcher::Gateway,
+            DispatchMode::ThreadPool { threads, ordering } => {
+                Dispatcher::ThreadPool(ThreadPool::new(threads, ordering))
+            }
+        }
+    }
+
+    pub fn dispatch<F>(&self, smalld: &SmallD, payload: Payload, f: F)
+    where
+        F: FnOnce(&SmallD, &Payload) + Send + 'static,
+    {
+        match self {
+            Dispatcher::Gateway => f(smalld, &payload),
+            Dispatcher::ThreadPool(pool) => {
+                let key = pool.ordering_key(&payload);
+                let smalld = smalld.clone();
+                pool.execute(key, Box::new(move || f(&smalld, &payload)));
+            }
+        }
+    }
+}
+
+pub(crate) struct ThreadPool {
+    queues: Vec<Sender<Job>>,
+    ordering: EventOrdering,
+    next: AtomicUsize,
+}
+
+impl ThreadPool {
+    fn new(threads: usize, ordering: EventOrdering) -> ThreadPool {
+        // unordered payloads share a single queue so that any idle thread can pick them up,
+        // ordered payloads have a queue per thread so payloads with the same key are handled
+        // by the same thread
+        let (queue_count, threads_per_queue) = match ordering {
+            EventOrdering::Unordered => (1, threads),
+            _ => (threads, 1),
+        };
+
+        let queues = (0..queue_count)
+            .map(|_| {
+                let (sender, receiver) = channel::<Job>();
+                let receiver = Arc::new(Mutex::new(receiver));
+
+                for _ in 0..threads_per_queue {
+                    let receiver = receiver.clone();
+                    spawn(move || Self::work(&receiver));
+                }
+
+                sender
+            })
+            .collect();
+
+        ThreadPool {
+            queues,
+            ordering,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    fn work(receiver: &Mutex<Receiver<Job>>) {
+        loop {
+            let job = receiver.lock().unwrap().recv();
+
+            match job {
+                Ok(job) => job(),
+                Err(_) => break,
+            }
+        }
+    }
+
+    fn ordering_key(&self, payload: &Payload) -> Option<u64> {
+        let field = |name: &str| {
+            payload
+                .d
+                .as_ref()
+                .and_then(|d| d.get(name))
+                .and_then(Value::as_str)
+        };
+
+        let guild_id = || {
+            field("guild_id").or_else(|| match payload.t.as_deref() {
+                Some(t) if GUILD_EVENTS.contains(&t) => field("id"),
+                _ => None,
+            })
+        };
+
+        let key = match self.ordering {
+            EventOrdering::Unordered => None,
+            EventOrdering::PerGuild => guild_id(),
+            EventOrdering::PerChannel => field("channel_id").or_else(guild_id),
+        };
+
+        key.map(|k| {
+            let mut hasher = DefaultHasher::new();
+            k.hash(&mut hasher);
+            hasher.finish()
+        })
+    }
+
+    fn execute(&self, key: Option<u64>, job: Job) {
+        let idx = match key {
+            Some(k) => (k % self.queues.len() as u64) as usize,
+            None => self.next.fetch_add(1, Ordering::Relaxed) % self.queues.len(),
+        };
+
+        // the receiving threads only exit once the sender is dropped, so this cannot fail
+        let _ = self.queues[idx].send(job);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::Op;
+    use crate::smalld::SmallDBuilder;
+    use serde_json::json;
+    use std::sync::mpsc::sync_channel;
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(2);
+
+    fn thread_pool_smalld() -> SmallD {
+        SmallDBuilder::new()
+            .token("token")
+            .dispatch_mode(DispatchMode::ThreadPool {
+                threads: 2,
+                ordering: EventOrdering::Unordered,
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn event(name: &str, d: Value) -> Payload {
+        let mut payload = Payload::op(Op::Dispatch);
+        payload.t(name).d(d);
+        payload
+    }
+
+    #[test]
+    fn it_orders_guild_events_by_guild_id() {
+        let pool = ThreadPool::new(4, EventOrdering::PerGuild);
+
+        assert_eq!(
+            pool.ordering_key(&event("GUILD_CREATE", json!({"id": "1"}))),
+            pool.ordering_key(&event("MESSAGE_CREATE", json!({"guild_id": "1"})))
+        );
+        assert_eq!(
+            pool.ordering_key(&event("MESSAGE_CREATE", json!({"id": "1"}))),
+            None
+        );
+    }
+
+    #[test]
+    fn it_does_not_delay_other_events_behind_a_slow_listener() {
+        let smalld = thread_pool_smalld();
+        let (release, blocked) = sync_channel::<()>(0);
+        let blocked = Mutex::new(blocked);
+        let (sender, received) = sync_channel(1);
+
+        smalld.on_event("MESSAGE_CREATE", move |_, _| {
+            let _ = blocked.lock().unwrap().recv_timeout(TIMEOUT);
+        });
+        smalld.on_event("GUILD_CREATE", move |_, d| {
+            let _ = sender.try_send(d.clone());
+        });
+
+        smalld.dispatch(event("MESSAGE_CREATE", json!({})));
+        smalld.dispatch(event("GUILD_CREATE", json!({"id": "1"})));
+
+        assert_eq!(received.recv_timeout(TIMEOUT / 2), Ok(json!({"id": "1"})));
+        let _ = release.try_send(());
+    }
+
+    #[test]
+    fn it_lets_listener_wait_for_event_in_the_same_channel() {
+        let smalld = SmallDBuilder::new()
+            .token("token")
+            .dispatch_mode(DispatchMode::ThreadPool {
+                threads: 2,
+                ordering: EventOrdering::PerChannel,
+            })
+            .build()
+            .unwrap();
+        let (sender, received) = sync_channel(1);
+
+        smalld.on_event("MESSAGE_CREATE", move |s, d| {
+            if d["content"] == "question" {
+                let reply =
+                    s.wait_for_event("MESSAGE_CREATE", |d| d["content"] == "answer", TIMEOUT);
+                let _ = sender.try_send(reply.map_err(|e| e.to_string()));
+            }
+        });
+
+        smalld.dispatch(event(
+            "MESSAGE_CREATE",
+            json!({"channel_id": "1", "content": "question"}),
+        ));
+
+        // The answer is repeated as it is missed if sent before the listener starts waiting
+        let answer = json!({"channel_id": "1", "content": "answer"});
+        let reply = (0..20).find_map(|_| {
+            smalld.dispatch(event("MESSAGE_CREATE", answer.clone()));
+            received.recv_timeout(TIMEOUT / 20).ok()
+        });
+
+        assert_eq!(reply, Some(Ok(answer)));
+    }
+
+    #[test]
+    fn it_calls_a_busy_listener_for_another_payload() {
+        let smalld = thread_pool_smalld();
+        let (release, blocked) = sync_channel::<()>(0);
+        let blocked = Mutex::new(blocked);
+        let (sender, received) = sync_channel(1);
+
+        smalld.on_event("MESSAGE_CREATE", move |_, d| {
+            if d["content"] == "slow" {
+                let _ = blocked.lock().unwrap().recv_timeout(TIMEOUT);
+            } else {
+                let _ = sender.try_send(d.clone());
+            }
+        });
+
+        smalld.dispatch(event("MESSAGE_CREATE", json!({"content": "slow"})));
+        smalld.dispatch(event("MESSAGE_CREATE", json!({"content": "fast"})));
+
+        assert_eq!(
+            received.recv_timeout(TIMEOUT / 2),
+            Ok(json!({"content": "fast"}))
+        );
+        let _ = release.try_send(());
+    }
+}
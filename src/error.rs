@@ -1,3 +1,4 @@
+use crate::discord_error::{FieldError, JsonErrorCode};
 use crate::retry::RetryableError;
 use thiserror::Error;
 
@@ -8,15 +9,29 @@ pub enum Error {
     ConfigurationError(String),
     IllegalArgumentError(String),
     IllegalStateError(String),
+    TimeoutError(String),
+    ListenerPanic(String),
+    CustomError(Box<dyn std::error::Error + Send + Sync>),
     HttpError(Box<ureq::Error>),
     WebSocketError(#[from] tungstenite::Error),
     IOError(#[from] std::io::Error),
+    JsonError(#[from] serde_json::Error),
 
     #[error("{code:?}: {reason}")]
     WebSocketClosed {
         code: Option<u16>,
         reason: String,
     },
+
+    /// An error response from the Discord API with a
+    /// [JSON error code](https://discord.com/developers/docs/topics/opcodes-and-status-codes#json).
+    #[error("{status} {code:?}: {message}")]
+    DiscordApi {
+        status: u16,
+        code: JsonErrorCode,
+        message: String,
+        errors: Vec<FieldError>,
+    },
 }
 
 impl From<ureq::Error> for Error {
@@ -29,6 +44,15 @@ impl Error {
     pub fn illegal_state<S: Into<String>>(msg: S) -> Error {
         Error::IllegalStateError(msg.into())
     }
+
+    /// Wraps an error from outside of SmallD, e.g. for returning it from a listener registered
+    /// via [`try_on_event`](crate::SmallD#method.try_on_event).
+    pub fn custom<E>(error: E) -> Error
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Error::CustomError(error.into())
+    }
 }
 
 const FATAL_WEBSOCKET_CODES: [u16; 6] = [4004, 4010, 4011, 4012, 4013, 4014];
@@ -0,0 +1,235 @@
+use crate::error::Error;
+use crate::listeners::ListenerHandle;
+use crate::payload::{Op, Payload};
+use crate::smalld::SmallD;
+use serde_json::Value;
+
+/// Builder for a listener to dispatch events matching a set of filters. Created via
+/// [`SmallD::events`](crate::SmallD#method.events).
+///
+/// All filters must match for the listener to be called. With no filters the listener is called
+/// for every dispatch event.
+///
+/// ```no_run
+/// use smalld::SmallD;
+///
+/// let smalld = SmallD::new().expect("Failed to initialize smalld");
+///
+/// smalld
+///   .events()
+///   .named(["MESSAGE_CREATE", "MESSAGE_UPDATE"])
+///   .channel("123456789012345678")
+///   .not_from_bots()
+///   .handle(|smalld, json| {
+///     println!("Message in channel: {:?}", json.get("content"));
+///   });
+/// ```
+pub struct EventFilter<'a> {
+    smalld: &'a SmallD,
+    names: Vec<String>,
+    guild_id: Option<String>,
+    channel_id: Option<String>,
+    not_from_bots: bool,
+}
+
+impl<'a> EventFilter<'a> {
+    pub(crate) fn new(smalld: &'a SmallD) -> Self {
+        EventFilter {
+            smalld,
+            names: Vec::new(),
+            guild_id: None,
+            channel_id: None,
+            not_from_bots: false,
+        }
+    }
+
+    /// Only matches events with one of the given names.
+    pub fn named<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.names.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only matches events with the given `guild_id`.
+    ///
+    /// Only the `guild_id` field is checked, so this never matches `GUILD_CREATE`,
+    /// `GUILD_UPDATE` or `GUILD_DELETE`, whose payload is the guild itself with its id in `id`.
+    pub fn guild<S: Into<String>>(mut self, guild_id: S) -> Self {
+        self.guild_id = Some(guild_id.into());
+        self
+    }
+
+    /// Only matches events with the given `channel_id`.
+    pub fn channel<S: Into<String>>(mut self, channel_id: S) -> Self {
+        self.channel_id = Some(channel_id.into());
+        self
+    }
+
+    /// Does not match events with an `author` that is a bot.
+    pub fn not_from_bots(mut self) -> Self {
+        self.not_from_bots = true;
+        self
+    }
+
+    /// Registers a listener for events matching the filters.
+    pub fn handle<F>(self, f: F) -> ListenerHandle
+    where
+        F: Fn(&SmallD, &Value) + Send + Sync + 'static,
+    {
+        self.try_handle(move |s, d| {
+            f(s, d);
+            Ok(())
+        })
+    }
+
+    /// Registers a fallible listener for events matching the filters. Errors are handled as for
+    /// [`try_on_event`](crate::SmallD#method.try_on_event).
+    pub fn try_handle<F>(self, f: F) -> ListenerHandle
+    where
+        F: Fn(&SmallD, &Value) -> Result<(), Error> + Send + Sync + 'static,
+    {
+        let filter = Filter {
+            names: self.names,
+            guild_id: self.guild_id,
+            channel_id: self.channel_id,
+            not_from_bots: self.not_from_bots,
+        };
+
+        self.smalld.try_on_gateway_payload(move |s, p| match p {
+            Payload {
+                op: Op::Dispatch,
+                t: Some(event_name),
+                d: Some(d),
+                ..
+            } if filter.matches(event_name, d) => f(s, d),
+            _ => Ok(()),
+        })
+    }
+}
+
+struct Filter {
+    names: Vec<String>,
+    guild_id: Option<String>,
+    channel_id: Option<String>,
+    not_from_bots: bool,
+}
+
+impl Filter {
+    fn matches(&self, event_name: &str, d: &Value) -> bool {
+        let field_is = |name: &str, expected: &Option<String>| match expected {
+            Some(expected) => d.get(name).and_then(Value::as_str) == Some(expected),
+            None => true,
+        };
+
+        let is_from_bot = || {
+            d.pointer("/author/bot")
+                .and_then(Value::as_bool)
+                .unwrap_or(false)
+        };
+
+        (self.names.is_empty() || self.names.iter().any(|n| n == event_name))
+            && field_is("guild_id", &self.guild_id)
+            && field_is("channel_id", &self.channel_id)
+            && !(self.not_from_bots && is_from_bot())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn filter() -> Filter {
+        Filter {
+            names: Vec::new(),
+            guild_id: None,
+            channel_id: None,
+            not_from_bots: false,
+        }
+    }
+
+    fn message(guild_id: &str, channel_id: &str, bot: bool) -> Value {
+        json!({
+            "guild_id": guild_id,
+            "channel_id": channel_id,
+            "author": { "id": "3", "bot": bot }
+        })
+    }
+
+    #[test]
+    fn it_matches_everything_without_filters() {
+        let filter = filter();
+
+        assert!(filter.matches("MESSAGE_CREATE", &message("1", "2", true)));
+        assert!(filter.matches("READY", &json!({})));
+    }
+
+    #[test]
+    fn it_matches_any_of_multiple_names() {
+        let filter = Filter {
+            names: vec!["MESSAGE_CREATE".to_string(), "MESSAGE_UPDATE".to_string()],
+            ..filter()
+        };
+
+        assert!(filter.matches("MESSAGE_CREATE", &json!({})));
+        assert!(filter.matches("MESSAGE_UPDATE", &json!({})));
+        assert!(!filter.matches("MESSAGE_DELETE", &json!({})));
+    }
+
+    #[test]
+    fn it_does_not_match_other_guild() {
+        let filter = Filter {
+            guild_id: Some("1".to_string()),
+            ..filter()
+        };
+
+        assert!(filter.matches("MESSAGE_CREATE", &message("1", "2", false)));
+        assert!(!filter.matches("MESSAGE_CREATE", &message("9", "2", false)));
+        assert!(!filter.matches("TYPING_START", &json!({ "channel_id": "2" })));
+        assert!(!filter.matches("GUILD_UPDATE", &json!({ "id": "1" })));
+    }
+
+    #[test]
+    fn it_does_not_match_other_channel() {
+        let filter = Filter {
+            channel_id: Some("2".to_string()),
+            ..filter()
+        };
+
+        assert!(filter.matches("MESSAGE_CREATE", &message("1", "2", false)));
+        assert!(!filter.matches("MESSAGE_CREATE", &message("1", "9", false)));
+        assert!(!filter.matches("GUILD_UPDATE", &json!({ "id": "1" })));
+    }
+
+    #[test]
+    fn it_does_not_match_messages_from_bots() {
+        let filter = Filter {
+            not_from_bots: true,
+            ..filter()
+        };
+
+        assert!(filter.matches("MESSAGE_CREATE", &message("1", "2", false)));
+        assert!(!filter.matches("MESSAGE_CREATE", &message("1", "2", true)));
+        assert!(filter.matches("MESSAGE_CREATE", &json!({ "author": { "id": "3" } })));
+        assert!(filter.matches("GUILD_UPDATE", &json!({ "id": "1" })));
+    }
+
+    #[test]
+    fn it_requires_all_filters_to_match() {
+        let filter = Filter {
+            names: vec!["MESSAGE_CREATE".to_string()],
+            guild_id: Some("1".to_string()),
+            channel_id: Some("2".to_string()),
+            not_from_bots: true,
+        };
+
+        assert!(filter.matches("MESSAGE_CREATE", &message("1", "2", false)));
+        assert!(!filter.matches("MESSAGE_UPDATE", &message("1", "2", false)));
+        assert!(!filter.matches("MESSAGE_CREATE", &message("9", "2", false)));
+        assert!(!filter.matches("MESSAGE_CREATE", &message("1", "9", false)));
+        assert!(!filter.matches("MESSAGE_CREATE", &message("1", "2", true)));
+    }
+}
@@ -1,18 +1,21 @@
 use crate::error::Error;
+use crate::network::NetworkConfig;
 use crate::payload::Payload;
 use log::{debug, warn};
 use std::borrow::Cow;
 use std::io::ErrorKind;
 use std::sync::Mutex;
 use tungstenite::client::AutoStream;
+use tungstenite::handshake::HandshakeError;
 use tungstenite::protocol::frame::CloseFrame;
 use tungstenite::stream::Stream;
-use tungstenite::{connect, Message as WsMessage, WebSocket};
+use tungstenite::{client, Message as WsMessage, WebSocket};
 use url::Url;
 
 type WS = WebSocket<AutoStream>;
 
 pub struct Gateway {
+    network: NetworkConfig,
     web_socket: Mutex<Option<WS>>,
 }
 
@@ -24,19 +27,31 @@ pub enum Message {
 }
 
 impl Gateway {
-    pub fn new() -> Gateway {
+    pub fn new(network: NetworkConfig) -> Gateway {
         Gateway {
+            network,
             web_socket: Mutex::new(None),
         }
     }
 
     pub fn connect(&self, url: Url) -> Result<(), Error> {
-        let (mut socket, _) = connect(url.as_str())?;
+        let stream = self.network.open_stream(&url)?;
 
-        match socket.get_mut() {
-            Stream::Plain(s) => s.set_nonblocking(true),
-            Stream::Tls(s) => s.get_mut().set_nonblocking(true),
-        }?;
+        let (mut socket, _) = client(url.as_str(), stream).map_err(|e| match e {
+            HandshakeError::Failure(err) => err.into(),
+            HandshakeError::Interrupted(_) => {
+                Error::illegal_state("Gateway handshake did not complete")
+            }
+        })?;
+
+        let tcp = match socket.get_mut() {
+            Stream::Plain(s) => s,
+            Stream::Tls(s) => s.get_mut(),
+        };
+
+        tcp.set_read_timeout(None)?;
+        tcp.set_write_timeout(None)?;
+        tcp.set_nonblocking(true)?;
 
         let mut lock = self.web_socket.lock().unwrap();
         *lock = Some(socket);
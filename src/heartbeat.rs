@@ -25,7 +25,7 @@ impl Heartbeat {
     }
 
     pub fn attach(self, smalld: &SmallD) {
-        smalld.on_gateway_payload(move |s, p| self.on_gateway_payload(s, p));
+        smalld.on_internal_payload(move |s, p| self.on_gateway_payload(s, p));
     }
 
     fn on_gateway_payload(&self, smalld: &SmallD, p: &Payload) {
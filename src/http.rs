@@ -1,60 +1,146 @@
 use crate::error::Error;
-use serde_json::{json, Value};
-use ureq::{Agent, AgentBuilder, Request, Response};
+use crate::multipart::Multipart;
+use crate::network::NetworkConfig;
+use crate::oauth2::{AccessToken, OAuth2Client};
+use crate::request::{Body, Method};
+use crate::response::Response;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use ureq::{Agent, Request};
+use url::form_urlencoded;
 use url::Url;
 
+/// Characters to be percent encoded in a URI component, matching javascript's
+/// `encodeURIComponent`.
+const URI_COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'!')
+    .remove(b'~')
+    .remove(b'*')
+    .remove(b'\'')
+    .remove(b'(')
+    .remove(b')');
+
+/// How long before expiry an OAuth2 access token is refreshed.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// The type of token used to authorize requests to Discord's resources api.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenType {
+    /// A bot token, sent as `Bot <token>`.
+    Bot,
+
+    /// An OAuth2 access token, sent as `Bearer <token>`.
+    Bearer,
+}
+
+pub(crate) enum Credentials {
+    Token(TokenType, String),
+    OAuth2(OAuth2Client, AccessToken),
+}
+
+#[derive(Clone)]
 pub(crate) struct Http {
-    authorization: String,
+    credentials: Option<Arc<Mutex<Credentials>>>,
     user_agent: String,
     base_url: Url,
     agent: Agent,
 }
 
 impl Http {
-    pub fn new<S: AsRef<str>>(token: S, base_url: Url) -> Http {
+    pub fn new(credentials: Option<Credentials>, base_url: Url, network: &NetworkConfig) -> Http {
         Http {
-            authorization: format!("Bot {}", token.as_ref()),
+            credentials: credentials.map(|c| Arc::new(Mutex::new(c))),
             user_agent: format!(
                 "DiscordBot ({}, {})",
                 env!("CARGO_PKG_REPOSITORY"),
                 env!("CARGO_PKG_VERSION")
             ),
             base_url,
-            agent: AgentBuilder::new().build(),
+            agent: network.agent(),
         }
     }
 
-    pub fn get<S: AsRef<str>>(&self, path: S, params: QueryParameters) -> Result<Value, Error> {
-        self.with_request("GET", path.as_ref(), params, |r| r.call())
+    /// A copy of this `Http` that sends no `Authorization` header, sharing the same connections.
+    pub fn without_authorization(&self) -> Http {
+        Http {
+            credentials: None,
+            ..self.clone()
+        }
     }
 
-    pub fn post<S: AsRef<str>>(
-        &self,
-        path: S,
-        params: QueryParameters,
-        json: Value,
-    ) -> Result<Value, Error> {
-        self.with_request("POST", path.as_ref(), params, |r| r.send_json(json))
-    }
+    /// The current OAuth2 access token, if authorizing with one.
+    pub fn access_token(&self) -> Option<AccessToken> {
+        let credentials = self.credentials.as_ref()?;
 
-    pub fn put<S: AsRef<str>>(
-        &self,
-        path: S,
-        params: QueryParameters,
-        json: Value,
-    ) -> Result<Value, Error> {
-        self.with_request("PUT", path.as_ref(), params, |r| r.send_json(json))
+        match &*credentials.lock().unwrap_or_else(PoisonError::into_inner) {
+            Credentials::OAuth2(_, token) => Some(token.clone()),
+            Credentials::Token(..) => None,
+        }
     }
 
-    pub fn patch<S: AsRef<str>>(&self, path: S, json: Value) -> Result<Value, Error> {
-        self.with_request("PATCH", path.as_ref(), QueryParameters::new(), |r| {
-            r.send_json(json)
-        })
+    /// The value for the `Authorization` header, refreshing the OAuth2 access token first if it
+    /// is about to expire. The lock is held while refreshing so that concurrent requests wait
+    /// for a single refresh.
+    fn authorization(&self) -> Result<Option<String>, Error> {
+        let credentials = match &self.credentials {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+
+        let mut credentials = credentials.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match &mut *credentials {
+            Credentials::Token(TokenType::Bot, token) => Ok(Some(format!("Bot {}", token))),
+            Credentials::Token(TokenType::Bearer, token) => Ok(Some(format!("Bearer {}", token))),
+            Credentials::OAuth2(client, token) => {
+                match &token.refresh_token {
+                    Some(refresh_token) if token.expires_within(TOKEN_REFRESH_MARGIN) => {
+                        let refresh_token = refresh_token.clone();
+                        let mut refreshed = client.refresh(refresh_token.as_str())?;
+                        refreshed.refresh_token.get_or_insert(refresh_token);
+                        *token = refreshed;
+                    }
+                    _ => (),
+                }
+
+                Ok(Some(format!("Bearer {}", token.access_token)))
+            }
+        }
     }
 
-    pub fn delete<S: AsRef<str>>(&self, path: S) -> Result<Value, Error> {
-        self.with_request("DELETE", path.as_ref(), QueryParameters::new(), |r| {
-            r.call()
+    pub fn send(
+        &self,
+        method: Method,
+        path: &str,
+        params: QueryParameters,
+        body: Body,
+        options: &RequestOptions,
+    ) -> Result<Response, Error> {
+        let content = match body {
+            Body::None => None,
+            Body::Json(json) => Some(("application/json".to_string(), serde_json::to_vec(&json)?)),
+            Body::Multipart(json, uploads) => {
+                let multipart = Multipart::new(&json, &uploads);
+                Some((multipart.content_type(), multipart.body().to_vec()))
+            }
+            Body::Form(fields) => {
+                let encoded = form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(fields)
+                    .finish();
+                Some((
+                    "application/x-www-form-urlencoded".to_string(),
+                    encoded.into_bytes(),
+                ))
+            }
+        };
+
+        self.with_request(method.as_str(), path, params, options, |r| match &content {
+            None => r.call(),
+            Some((content_type, bytes)) => r.set("Content-Type", content_type).send_bytes(bytes),
         })
     }
 
@@ -74,24 +160,27 @@ impl Http {
         method: &str,
         path: &str,
         params: QueryParameters,
+        options: &RequestOptions,
         f: F,
-    ) -> Result<Value, Error>
+    ) -> Result<Response, Error>
     where
-        F: FnOnce(Request) -> Result<Response, ureq::Error>,
+        F: FnOnce(Request) -> Result<ureq::Response, ureq::Error>,
     {
         let mut request = self
             .agent
             .request_url(method, &self.build_url(path)?)
-            .set("Authorization", &self.authorization)
             .set("User-Agent", &self.user_agent);
 
-        request = params.apply_to(request);
+        if let Some(authorization) = self.authorization()? {
+            request = request.set("Authorization", &authorization);
+        }
 
-        let response = f(request)?;
+        request = params.apply_to(request);
+        request = options.apply_to(request);
 
-        match response.status() {
-            204 => Ok(json!({})),
-            _ => response.into_json().map_err(|e| e.into()),
+        match f(request) {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => Response::from_ureq(response),
+            Err(err) => Err(err.into()),
         }
     }
 }
@@ -138,3 +227,70 @@ impl Default for QueryParameters {
         Self::new()
     }
 }
+
+/// Options to be applied to a HTTP request, in addition to the method, url, and body.
+///
+/// ```no_run
+/// use smalld::RequestOptions;
+/// use std::time::Duration;
+///
+/// let options = RequestOptions::new()
+///     .reason("Spamming in #general")
+///     .header("X-Custom-Header", "value")
+///     .timeout(Duration::from_secs(10));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RequestOptions {
+    reason: Option<String>,
+    headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+}
+
+impl RequestOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the reason to be shown in the guild's audit log for the request. The reason is sent
+    /// url encoded in the `X-Audit-Log-Reason` header.
+    pub fn reason<S: Into<String>>(mut self, reason: S) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    /// Adds a header to the request. The header is sent as is, so must be valid for a HTTP
+    /// header.
+    pub fn header<A, B>(mut self, name: A, value: B) -> Self
+    where
+        A: Into<String>,
+        B: Into<String>,
+    {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the timeout for the whole request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub(self) fn apply_to(&self, req_in: Request) -> Request {
+        let mut req_out = req_in;
+
+        for (k, v) in self.headers.iter() {
+            req_out = req_out.set(k, v);
+        }
+
+        if let Some(reason) = &self.reason {
+            let encoded = utf8_percent_encode(reason, URI_COMPONENT).to_string();
+            req_out = req_out.set("X-Audit-Log-Reason", &encoded);
+        }
+
+        if let Some(timeout) = self.timeout {
+            req_out = req_out.timeout(timeout);
+        }
+
+        req_out
+    }
+}
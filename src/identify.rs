@@ -23,7 +23,7 @@ impl Identify {
     }
 
     pub fn attach(mut self, smalld: &SmallD) {
-        smalld.on_gateway_payload(move |s, p| self.on_gateway_payload(s, p));
+        smalld.on_internal_payload(move |s, p| self.on_gateway_payload(s, p));
     }
 
     fn on_gateway_payload(&mut self, smalld: &SmallD, p: &Payload) {
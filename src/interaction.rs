@@ -0,0 +1,325 @@
+use crate::error::Error;
+use crate::smalld::SmallD;
+use crate::snowflake::Snowflake;
+use crate::webhook::Webhook;
+use serde_json::{json, Value};
+#[cfg(feature = "interactions-server")]
+use std::collections::HashMap;
+#[cfg(feature = "interactions-server")]
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+#[cfg(feature = "interactions-server")]
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// How long Discord waits for the initial response to an interaction.
+const RESPONSE_DEADLINE: Duration = Duration::from_secs(3);
+
+/// [Interaction callback types](https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-response-object-interaction-callback-type).
+const CHANNEL_MESSAGE_WITH_SOURCE: u8 = 4;
+const DEFERRED_CHANNEL_MESSAGE_WITH_SOURCE: u8 = 5;
+const DEFERRED_UPDATE_MESSAGE: u8 = 6;
+const UPDATE_MESSAGE: u8 = 7;
+const APPLICATION_COMMAND_AUTOCOMPLETE_RESULT: u8 = 8;
+const MODAL: u8 = 9;
+
+/// An [interaction](https://discord.com/developers/docs/interactions/receiving-and-responding),
+/// such as a slash command or button press, as received by a handler registered via
+/// [`SmallD::on_interaction`](SmallD#method.on_interaction).
+///
+/// Discord requires an initial response within three seconds, either one of the responses
+/// such as [`respond`](Interaction#method.respond), or [`defer`](Interaction#method.defer) to
+/// respond later via [`edit_original`](Interaction#method.edit_original). Followup messages,
+/// and editing or deleting the original response, are authorized by the interaction's token
+/// rather than the bot token, and are possible for 15 minutes after the interaction.
+///
+/// ```no_run
+/// use serde_json::json;
+/// use smalld::SmallD;
+///
+/// let smalld = SmallD::new().expect("Failed to initialize smalld");
+///
+/// smalld.try_on_interaction(|interaction| {
+///   match interaction.command_name() {
+///     Some("ping") => interaction.respond(json!({"content": "pong"})),
+///     Some("report") => {
+///       interaction.defer()?;
+///       let report = "..."; // Something that takes a while
+///       interaction.edit_original(json!({"content": report}))?;
+///       Ok(())
+///     }
+///     _ => Ok(()),
+///   }
+/// });
+///
+/// smalld.run();
+/// ```
+#[derive(Clone)]
+pub struct Interaction {
+    smalld: SmallD,
+    data: Value,
+    received_at: Instant,
+}
+
+impl Interaction {
+    pub(crate) fn new(smalld: SmallD, data: Value) -> Interaction {
+        Interaction {
+            smalld,
+            data,
+            received_at: Instant::now(),
+        }
+    }
+
+    pub fn smalld(&self) -> &SmallD {
+        &self.smalld
+    }
+
+    /// The interaction object as received from Discord.
+    pub fn data(&self) -> &Value {
+        &self.data
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.data["id"].as_str()
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.data["token"].as_str()
+    }
+
+    pub fn application_id(&self) -> Option<&str> {
+        self.data["application_id"].as_str()
+    }
+
+    /// The [type](https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-object-interaction-type)
+    /// of the interaction.
+    pub fn kind(&self) -> Option<u64> {
+        self.data["type"].as_u64()
+    }
+
+    pub fn guild_id(&self) -> Option<&str> {
+        self.data["guild_id"].as_str()
+    }
+
+    pub fn channel_id(&self) -> Option<&str> {
+        self.data["channel_id"].as_str()
+    }
+
+    /// The user that triggered the interaction, whether in a guild or a DM.
+    pub fn user(&self) -> Option<&Value> {
+        self.data
+            .pointer("/member/user")
+            .or_else(|| self.data.get("user"))
+    }
+
+    /// The name of the invoked command, for application command and autocomplete interactions.
+    pub fn command_name(&self) -> Option<&str> {
+        self.data.pointer("/data/name").and_then(Value::as_str)
+    }
+
+    /// The custom id of the component or modal, for component and modal submit interactions.
+    pub fn custom_id(&self) -> Option<&str> {
+        self.data.pointer("/data/custom_id").and_then(Value::as_str)
+    }
+
+    /// Time left to send the initial response before Discord considers the interaction failed.
+    pub fn time_remaining(&self) -> Duration {
+        RESPONSE_DEADLINE
+            .checked_sub(self.received_at.elapsed())
+            .unwrap_or_default()
+    }
+
+    /// Responds with a message. `json` is the
+    /// [message](https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-response-object-messages)
+    /// to send, e.g. `{"content": "Hello", "flags": 64}` for an ephemeral message.
+    pub fn respond(&self, json: Value) -> Result<(), Error> {
+        self.callback(CHANNEL_MESSAGE_WITH_SOURCE, Some(json))
+    }
+
+    /// Acknowledges the interaction, showing a loading state until the response is set via
+    /// [`edit_original`](Interaction#method.edit_original).
+    pub fn defer(&self) -> Result<(), Error> {
+        self.callback(DEFERRED_CHANNEL_MESSAGE_WITH_SOURCE, None)
+    }
+
+    /// Same as [`defer`](Interaction#method.defer), but the response will be ephemeral.
+    pub fn defer_ephemeral(&self) -> Result<(), Error> {
+        self.callback(
+            DEFERRED_CHANNEL_MESSAGE_WITH_SOURCE,
+            Some(json!({"flags": 64})),
+        )
+    }
+
+    /// Acknowledges a component interaction, to edit the message it is attached to later.
+    pub fn defer_update(&self) -> Result<(), Error> {
+        self.callback(DEFERRED_UPDATE_MESSAGE, None)
+    }
+
+    /// Responds to a component interaction by editing the message it is attached to.
+    pub fn update_message(&self, json: Value) -> Result<(), Error> {
+        self.callback(UPDATE_MESSAGE, Some(json))
+    }
+
+    /// Responds to an autocomplete interaction with a list of choices, each with a `name` and
+    /// `value`.
+    pub fn autocomplete(&self, choices: Value) -> Result<(), Error> {
+        self.callback(
+            APPLICATION_COMMAND_AUTOCOMPLETE_RESULT,
+            Some(json!({ "choices": choices })),
+        )
+    }
+
+    /// Responds with a [modal](https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-response-object-modal)
+    /// for the user to fill in.
+    pub fn modal(&self, json: Value) -> Result<(), Error> {
+        self.callback(MODAL, Some(json))
+    }
+
+    fn callback(&self, kind: u8, data: Option<Value>) -> Result<(), Error> {
+        let mut response = json!({ "type": kind });
+
+        if let Some(data) = data {
+            response["data"] = data;
+        }
+
+        let id = self.require("id", self.id())?;
+
+        // Interactions received by an InteractionServer are responded to via the HTTP response
+        #[cfg(feature = "interactions-server")]
+        let response = match self.smalld.pending_responses().respond(id, response) {
+            Ok(()) => return Ok(()),
+            Err(response) => response,
+        };
+
+        let path = format!(
+            "/interactions/{}/{}/callback",
+            id,
+            self.require("token", self.token())?
+        );
+
+        self.smalld.post(path, response)?;
+
+        Ok(())
+    }
+
+    /// A [`Webhook`](Webhook) for followup messages, authorized by the interaction's token.
+    pub fn webhook(&self) -> Result<Webhook, Error> {
+        let application_id = self.require("application_id", self.application_id())?;
+        let token = self.require("token", self.token())?;
+
+        let application_id: Snowflake = application_id.parse().map_err(|_e| {
+            Error::illegal_state(format!("Bad application_id: {}", application_id))
+        })?;
+
+        Ok(self.smalld.webhook(application_id, token))
+    }
+
+    /// Sends a followup message, returning the message created.
+    pub fn followup(&self, json: Value) -> Result<Value, Error> {
+        self.webhook()?.execute(json).wait(true).send()
+    }
+
+    pub fn edit_followup<S: AsRef<str>>(&self, message_id: S, json: Value) -> Result<Value, Error> {
+        self.webhook()?.edit_message(message_id, json)
+    }
+
+    pub fn delete_followup<S: AsRef<str>>(&self, message_id: S) -> Result<(), Error> {
+        self.webhook()?.delete_message(message_id)?;
+        Ok(())
+    }
+
+    pub fn get_original(&self) -> Result<Value, Error> {
+        self.webhook()?.get_message("@original")
+    }
+
+    /// Edits the initial response, or sets it after a [`defer`](Interaction#method.defer).
+    pub fn edit_original(&self, json: Value) -> Result<Value, Error> {
+        self.webhook()?.edit_message("@original", json)
+    }
+
+    pub fn delete_original(&self) -> Result<(), Error> {
+        self.webhook()?.delete_message("@original")?;
+        Ok(())
+    }
+
+    fn require<'a>(&self, name: &str, value: Option<&'a str>) -> Result<&'a str, Error> {
+        value.ok_or_else(|| Error::illegal_state(format!("Interaction has no {}", name)))
+    }
+}
+
+/// Interactions received by an [`InteractionServer`](crate::InteractionServer) that are waiting
+/// for their initial response.
+#[cfg(feature = "interactions-server")]
+#[derive(Default)]
+pub(crate) struct PendingResponses(Mutex<HashMap<String, SyncSender<Value>>>);
+
+#[cfg(feature = "interactions-server")]
+impl PendingResponses {
+    /// Registers that a response is expected for the interaction with the given id.
+    pub fn expect(&self, id: &str) -> Receiver<Value> {
+        let (sender, receiver) = sync_channel(1);
+        self.lock().insert(id.to_string(), sender);
+        receiver
+    }
+
+    /// Waits for the response to the interaction until Discord's deadline passes.
+    pub fn wait(&self, id: &str, receiver: Receiver<Value>) -> Option<Value> {
+        self.wait_for(id, receiver, RESPONSE_DEADLINE)
+    }
+
+    fn wait_for(&self, id: &str, receiver: Receiver<Value>, timeout: Duration) -> Option<Value> {
+        if let Ok(response) = receiver.recv_timeout(timeout) {
+            return Some(response);
+        }
+
+        // A response sent before the entry is removed is still in the channel, while one sent
+        // after finds no entry and so is sent via the callback endpoint instead
+        self.lock().remove(id);
+        receiver.try_recv().ok()
+    }
+
+    /// Sends the response to an interaction waiting for it, or returns it if there is none.
+    pub fn respond(&self, id: &str, response: Value) -> Result<(), Value> {
+        // The lock is held while sending so that the waiting side cannot give up in between
+        let mut pending = self.lock();
+
+        match pending.remove(id) {
+            Some(sender) => sender.try_send(response).map_err(|e| match e {
+                TrySendError::Full(r) | TrySendError::Disconnected(r) => r,
+            }),
+            None => Err(response),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, SyncSender<Value>>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(all(test, feature = "interactions-server"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_passes_response_to_waiting_request() {
+        let pending = PendingResponses::default();
+        let receiver = pending.expect("1");
+
+        assert_eq!(pending.respond("1", json!({"type": 4})), Ok(()));
+        assert_eq!(pending.wait("1", receiver), Some(json!({"type": 4})));
+    }
+
+    #[test]
+    fn it_returns_response_given_after_waiting_request_gave_up() {
+        let pending = PendingResponses::default();
+        let receiver = pending.expect("1");
+
+        assert_eq!(
+            pending.wait_for("1", receiver, Duration::from_millis(10)),
+            None
+        );
+        assert_eq!(
+            pending.respond("1", json!({"type": 4})),
+            Err(json!({"type": 4}))
+        );
+    }
+}
@@ -0,0 +1,371 @@
+use crate::error::Error;
+use crate::payload::{Op, Payload};
+use crate::smalld::SmallD;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use log::{debug, warn};
+use serde_json::{json, Value};
+use std::convert::TryFrom;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+/// Limit on the size of a request body, well above the size of any interaction.
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Limit on the size of the request line and headers together.
+const MAX_HEADER_SIZE: u64 = 16 * 1024;
+
+/// Timeout for reading a request from, or writing a response to, a connection.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Receives [interactions](https://discord.com/developers/docs/interactions/receiving-and-responding#receiving-an-interaction)
+/// via HTTP requests from Discord, rather than the gateway. Available with the
+/// `interactions-server` feature.
+///
+/// Each request's signature is verified against the application's public key, PINGs are
+/// answered, and all other interactions are passed to the handlers registered via
+/// [`SmallD::on_interaction`](SmallD#method.on_interaction), as they would be if received from
+/// the gateway. The initial response of a handler, e.g. via
+/// [`Interaction::respond`](crate::Interaction#method.respond), is sent as the HTTP response.
+///
+/// Either run the server built in with [`serve`](InteractionServer#method.serve), or pass
+/// requests from another HTTP server to [`handle`](InteractionServer#method.handle).
+///
+/// ```no_run
+/// use serde_json::json;
+/// use smalld::{InteractionServer, SmallD};
+///
+/// let smalld = SmallD::new().expect("Failed to initialize smalld");
+///
+/// smalld.try_on_interaction(|interaction| {
+///   interaction.respond(json!({"content": "pong"}))
+/// });
+///
+/// InteractionServer::new(&smalld, "<application public key>")
+///   .expect("Bad public key")
+///   .serve("0.0.0.0:8080")
+///   .expect("Failed to run server");
+/// ```
+#[derive(Clone)]
+pub struct InteractionServer {
+    smalld: SmallD,
+    public_key: VerifyingKey,
+}
+
+/// The HTTP response to be sent for a request passed to
+/// [`InteractionServer::handle`](InteractionServer#method.handle). The body is always json.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl ServerResponse {
+    fn json(status: u16, json: Value) -> ServerResponse {
+        ServerResponse {
+            status,
+            body: json.to_string(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> ServerResponse {
+        ServerResponse::json(status, json!({ "error": message }))
+    }
+}
+
+impl InteractionServer {
+    /// Creates a server dispatching to the handlers of `smalld`. `public_key` is the hex encoded
+    /// public key shown for the application in the developer portal.
+    pub fn new<S: AsRef<str>>(smalld: &SmallD, public_key: S) -> Result<InteractionServer, Error> {
+        let error = || Error::ConfigurationError("Bad public key".to_string());
+
+        let bytes = hex::decode(public_key.as_ref().trim()).map_err(|_e| error())?;
+        let bytes = <[u8; 32]>::try_from(bytes.as_slice()).map_err(|_e| error())?;
+        let public_key = VerifyingKey::from_bytes(&bytes).map_err(|_e| error())?;
+
+        Ok(InteractionServer {
+            smalld: smalld.clone(),
+            public_key,
+        })
+    }
+
+    /// Whether `signature`, from the `X-Signature-Ed25519` header, is valid for the
+    /// `timestamp`, from the `X-Signature-Timestamp` header, and body of a request.
+    pub fn verify(&self, signature: &str, timestamp: &str, body: &[u8]) -> bool {
+        let signature = match hex::decode(signature)
+            .ok()
+            .and_then(|s| <[u8; 64]>::try_from(s.as_slice()).ok())
+        {
+            Some(s) => Signature::from_bytes(&s),
+            None => return false,
+        };
+
+        let mut message = timestamp.as_bytes().to_vec();
+        message.extend_from_slice(body);
+
+        self.public_key.verify(&message, &signature).is_ok()
+    }
+
+    /// Handles a request from Discord given its signature headers and body, returning the
+    /// response to send. Blocks until a handler responds to the interaction, or until Discord's
+    /// deadline for a response passes.
+    pub fn handle(
+        &self,
+        signature: Option<&str>,
+        timestamp: Option<&str>,
+        body: &[u8],
+    ) -> ServerResponse {
+        match (signature, timestamp) {
+            (Some(signature), Some(timestamp)) if self.verify(signature, timestamp, body) => (),
+            _ => return ServerResponse::error(401, "Invalid request signature"),
+        }
+
+        let interaction: Value = match serde_json::from_slice(body) {
+            Ok(json) => json,
+            Err(_) => return ServerResponse::error(400, "Bad json"),
+        };
+
+        match interaction["type"].as_u64() {
+            Some(1) => return ServerResponse::json(200, json!({"type": 1})),
+            Some(_) => (),
+            None => return ServerResponse::error(400, "No interaction type"),
+        }
+
+        let id = match interaction["id"].as_str() {
+            Some(id) => id.to_string(),
+            None => return ServerResponse::error(400, "No interaction id"),
+        };
+
+        let pending = self.smalld.pending_responses();
+        let response = pending.expect(&id);
+
+        let mut payload = Payload::op(Op::Dispatch);
+        payload.t("INTERACTION_CREATE").d(interaction);
+
+        // Dispatch on another thread so that the response is sent as soon as it is given, even
+        // if listeners are called on the dispatching thread and continue after responding
+        let smalld = self.smalld.clone();
+        thread::spawn(move || smalld.dispatch(payload));
+
+        match pending.wait(&id, response) {
+            Some(json) => ServerResponse::json(200, json),
+            None => {
+                warn!("No response to interaction {}", id);
+                ServerResponse::error(500, "No response to interaction")
+            }
+        }
+    }
+
+    /// Runs a HTTP server on `addr`, blocking forever unless binding fails. Each request is
+    /// handled on its own thread.
+    pub fn serve<A: ToSocketAddrs>(&self, addr: A) -> Result<(), Error> {
+        let listener = TcpListener::bind(addr)?;
+
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let server = self.clone();
+                    thread::spawn(move || {
+                        if let Err(err) = server.handle_connection(stream) {
+                            debug!("Error handling interactions request: {}", err);
+                        }
+                    });
+                }
+                Err(err) => warn!("Error accepting interactions connection: {}", err),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_connection(&self, stream: TcpStream) -> Result<(), Error> {
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+        let mut reader = BufReader::new(&stream);
+
+        let response = match RequestHead::read(&mut reader)? {
+            None => ServerResponse::error(431, "Request header fields too large"),
+            Some(head) if !head.request_line.starts_with("POST ") => {
+                ServerResponse::error(405, "Method not allowed")
+            }
+            Some(head) if head.content_length > MAX_BODY_SIZE => {
+                ServerResponse::error(413, "Request too large")
+            }
+            Some(head) => {
+                let mut body = vec![0; head.content_length];
+                reader.read_exact(&mut body)?;
+                self.handle(head.signature.as_deref(), head.timestamp.as_deref(), &body)
+            }
+        };
+
+        write!(
+            &stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            response.status,
+            reason_phrase(response.status),
+            response.body.len(),
+            response.body
+        )?;
+
+        Ok(())
+    }
+}
+
+/// The request line and the headers of a request needed for handling it.
+struct RequestHead {
+    request_line: String,
+    signature: Option<String>,
+    timestamp: Option<String>,
+    content_length: usize,
+}
+
+impl RequestHead {
+    /// Reads the request line and headers, leaving `reader` at the start of the body. Returns
+    /// `None` if they are larger than `MAX_HEADER_SIZE`.
+    fn read<R: BufRead>(reader: &mut R) -> Result<Option<RequestHead>, Error> {
+        let mut limited = reader.take(MAX_HEADER_SIZE);
+
+        let mut head = RequestHead {
+            request_line: String::new(),
+            signature: None,
+            timestamp: None,
+            content_length: 0,
+        };
+
+        limited.read_line(&mut head.request_line)?;
+
+        loop {
+            let mut line = String::new();
+            limited.read_line(&mut line)?;
+
+            if !line.ends_with('\n') && limited.limit() == 0 {
+                return Ok(None);
+            }
+
+            if line.trim().is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = line.split_once(':') {
+                let value = value.trim().to_string();
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "x-signature-ed25519" => head.signature = Some(value),
+                    "x-signature-timestamp" => head.timestamp = Some(value),
+                    "content-length" => head.content_length = value.parse().unwrap_or(0),
+                    _ => (),
+                }
+            }
+        }
+
+        Ok(Some(head))
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smalld::SmallDBuilder;
+    use ed25519_dalek::{Signer, SigningKey};
+    use std::io::Cursor;
+
+    const TIMESTAMP: &str = "1700000000";
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn server() -> InteractionServer {
+        let smalld = SmallDBuilder::new().token("token").build().unwrap();
+        let public_key = hex::encode(signing_key().verifying_key().to_bytes());
+        InteractionServer::new(&smalld, public_key).unwrap()
+    }
+
+    fn sign(body: &[u8]) -> String {
+        let mut message = TIMESTAMP.as_bytes().to_vec();
+        message.extend_from_slice(body);
+        hex::encode(signing_key().sign(&message).to_bytes())
+    }
+
+    #[test]
+    fn it_verifies_signature() {
+        let body = br#"{"type":1}"#;
+
+        assert!(server().verify(&sign(body), TIMESTAMP, body));
+        assert!(!server().verify(&sign(body), "1700000001", body));
+        assert!(!server().verify(&sign(body), TIMESTAMP, br#"{"type":2}"#));
+        assert!(!server().verify("not hex", TIMESTAMP, body));
+    }
+
+    #[test]
+    fn it_rejects_bad_signature() {
+        let body = br#"{"type":1}"#;
+        let bad = sign(br#"{"type":2}"#);
+
+        assert_eq!(
+            server().handle(Some(&bad), Some(TIMESTAMP), body).status,
+            401
+        );
+        assert_eq!(server().handle(None, Some(TIMESTAMP), body).status, 401);
+    }
+
+    #[test]
+    fn it_responds_to_ping() {
+        let body = br#"{"id":"1","type":1}"#;
+        let signature = sign(body);
+
+        assert_eq!(
+            server().handle(Some(&signature), Some(TIMESTAMP), body),
+            ServerResponse {
+                status: 200,
+                body: r#"{"type":1}"#.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn it_rejects_bad_public_key() {
+        let smalld = SmallDBuilder::new().token("token").build().unwrap();
+        assert!(InteractionServer::new(&smalld, "abcd").is_err());
+    }
+
+    #[test]
+    fn it_reads_request_head() {
+        let request = "POST / HTTP/1.1\r\nX-Signature-Ed25519: ab\r\nx-signature-timestamp: 1\r\nContent-Length: 4\r\n\r\nbody";
+        let mut reader = Cursor::new(request);
+
+        let head = RequestHead::read(&mut reader).unwrap().unwrap();
+
+        assert_eq!(head.request_line, "POST / HTTP/1.1\r\n");
+        assert_eq!(head.signature.as_deref(), Some("ab"));
+        assert_eq!(head.timestamp.as_deref(), Some("1"));
+        assert_eq!(head.content_length, 4);
+
+        let mut body = String::new();
+        reader.read_to_string(&mut body).unwrap();
+        assert_eq!(body, "body");
+    }
+
+    #[test]
+    fn it_rejects_oversized_request_head() {
+        let long_header = format!("X-Long: {}\r\n", "a".repeat(MAX_HEADER_SIZE as usize));
+        let request = format!("POST / HTTP/1.1\r\n{}\r\n", long_header);
+
+        let head = RequestHead::read(&mut Cursor::new(request)).unwrap();
+
+        assert!(head.is_none());
+    }
+}
@@ -15,6 +15,9 @@
 //!   * Caching
 //!   * Command Framework
 //!
+//! Though for simple bots a lightweight [`CommandRouter`](CommandRouter) for prefix and slash
+//! commands is available with the optional `commands` feature.
+//!
 //! # Getting Started
 //!
 //! After you have [created a new
@@ -73,6 +76,16 @@
 //!
 //! smalld.run();
 //! ```
+//!
+//! Registering a listener returns a [`ListenerHandle`](ListenerHandle) that may be used to
+//! remove it again, which is useful for listeners only needed for a limited time.
+//!
+//! By default listeners are called one at a time on the thread receiving from the gateway, so a
+//! slow listener delays every other event. To call listeners on a pool of threads instead,
+//! configure a [`DispatchMode`](DispatchMode) via
+//! [`dispatch_mode`](smalld::SmallDBuilder#method.dispatch_mode). As a listener may then be
+//! called on several threads at once listeners are `Fn` rather than `FnMut`, and any state they
+//! mutate must be kept behind a `Mutex` or atomic.
 //!   
 //! To send requests through Discord's resources api SmallD provides methods related to the HTTP
 //! methods. For example, [`post`](smalld::SmallD#method.post) for sending a HTTP post request.
@@ -95,19 +108,62 @@
 //! }
 //! ```
 
+pub use crate::application_commands::{
+    ApplicationCommand, CommandChoice, CommandOption, CommandOptionType, CommandScope,
+};
+#[cfg(feature = "commands")]
+pub use crate::commands::{Args, Command, CommandContext, CommandRouter, Rejection};
+pub use crate::discord_error::{FieldError, JsonErrorCode};
+pub use crate::dispatch::{DispatchMode, EventOrdering};
 pub use crate::error::Error;
-pub use crate::http::QueryParameters;
+pub use crate::events::EventFilter;
+pub use crate::http::{QueryParameters, RequestOptions, TokenType};
 pub use crate::intents::Intent;
+pub use crate::interaction::Interaction;
+#[cfg(feature = "interactions-server")]
+pub use crate::interactions_server::{InteractionServer, ServerResponse};
+pub use crate::listeners::{ListenerGuard, ListenerHandle};
+pub use crate::middleware::Next;
+pub use crate::multipart::Upload;
+pub use crate::oauth2::{AccessToken, OAuth2Client};
+pub use crate::pagination::{PaginationKind, Paginator};
 pub use crate::payload::{Op, Payload};
+pub use crate::request::{Method, RequestBuilder};
+pub use crate::response::Response;
 pub use crate::smalld::{SmallD, SmallDBuilder};
+pub use crate::snowflake::Snowflake;
+pub use crate::webhook::{Webhook, WebhookExecution};
 
+mod application_commands;
+mod discord_error;
+mod dispatch;
 mod error;
+mod events;
 mod gateway;
 mod heartbeat;
 mod http;
 mod identify;
 mod intents;
+mod interaction;
 mod listeners;
+mod middleware;
+mod multipart;
+mod network;
+mod oauth2;
+mod pagination;
 mod payload;
+mod request;
+mod response;
 mod retry;
 mod smalld;
+mod snowflake;
+mod webhook;
+
+#[cfg(feature = "commands")]
+mod commands;
+
+#[cfg(feature = "models")]
+pub mod models;
+
+#[cfg(feature = "interactions-server")]
+mod interactions_server;
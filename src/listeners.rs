@@ -1,29 +1,328 @@
-use crate::payload::Payload;
+use crate::error::Error;
+use crate::payload::{Op, Payload};
 use crate::smalld::SmallD;
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 
-pub type Listener = dyn FnMut(&SmallD, &Payload) + Send + Sync + 'static;
+pub type Listener = dyn Fn(&SmallD, &Payload) + Send + Sync + 'static;
 
+#[derive(Clone)]
+struct Entry {
+    /// The dispatch event the listener is for, or `None` if it is for all payloads.
+    event: Option<&'static str>,
+    listener: Arc<Listener>,
+}
+
+impl Entry {
+    fn wants(&self, payload: &Payload) -> bool {
+        match self.event {
+            Some(name) => matches!(payload.op, Op::Dispatch) && payload.t.as_deref() == Some(name),
+            None => true,
+        }
+    }
+}
+
+type Entries = Mutex<BTreeMap<u64, Entry>>;
+
+/// Registry of listeners. The registry is only locked while adding, removing, or taking a
+/// snapshot of the listeners, never while a listener is running. This allows listeners to add
+/// or remove listeners, including themselves, while a payload is being dispatched, and allows
+/// a listener to be called for multiple payloads at once when dispatching on a thread pool.
+///
+/// A panic in a listener is caught and reported, so it affects neither the other listeners nor
+/// the connection to the gateway.
 pub struct Listeners {
-    listeners: Vec<Box<Listener>>,
+    next_id: AtomicU64,
+    entries: Arc<Entries>,
 }
 
 impl Listeners {
     pub fn new() -> Listeners {
         Listeners {
-            listeners: Vec::new(),
+            next_id: AtomicU64::new(0),
+            entries: Arc::new(Mutex::new(BTreeMap::new())),
         }
     }
 
-    pub fn add<F>(&mut self, f: F)
+    pub fn add<F>(&self, f: F) -> ListenerHandle
+    where
+        F: Fn(&SmallD, &Payload) + Send + Sync + 'static,
+    {
+        self.add_with_handle(None, |_| f)
+    }
+
+    /// Adds the listener created by `make`, which is given the handle of the listener being
+    /// added so that the listener may remove itself. If `event` is given the listener is only
+    /// called for dispatch events with that name.
+    pub fn add_with_handle<M, F>(&self, event: Option<&'static str>, make: M) -> ListenerHandle
     where
-        F: FnMut(&SmallD, &Payload) + Send + Sync + 'static,
+        M: FnOnce(ListenerHandle) -> F,
+        F: Fn(&SmallD, &Payload) + Send + Sync + 'static,
     {
-        self.listeners.push(Box::new(f));
+        let handle = ListenerHandle {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            entries: Arc::downgrade(&self.entries),
+        };
+
+        let entry = Entry {
+            event,
+            listener: Arc::new(make(handle.clone())),
+        };
+        self.entries.lock().unwrap().insert(handle.id, entry);
+
+        handle
+    }
+
+    /// Notifies the listeners registered at the time of the call. Listeners added while
+    /// notifying will first be notified of the next payload.
+    pub fn notify(&self, smalld: &SmallD, payload: &Payload) {
+        for (id, entry) in self.snapshot(payload) {
+            // a listener earlier in this notify may have removed this one
+            if !self.contains(id) {
+                continue;
+            }
+
+            let result =
+                panic::catch_unwind(AssertUnwindSafe(|| (entry.listener)(smalld, payload)));
+
+            if let Err(panic) = result {
+                let error = Error::ListenerPanic(panic_message(&panic));
+                smalld.report_listener_error(&error, payload);
+            }
+        }
+    }
+
+    /// The listeners that want `payload`, so that listeners for other events are skipped
+    /// without being called.
+    fn snapshot(&self, payload: &Payload) -> Vec<(u64, Entry)> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, entry)| entry.wants(payload))
+            .map(|(id, entry)| (*id, entry.clone()))
+            .collect()
+    }
+
+    fn contains(&self, id: u64) -> bool {
+        self.entries.lock().unwrap().contains_key(&id)
+    }
+}
+
+pub(crate) fn panic_message(panic: &Box<dyn Any + Send>) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Unknown panic".to_string())
+}
+
+/// Handle to a listener registered via [`on_event`](crate::SmallD#method.on_event) or
+/// [`on_gateway_payload`](crate::SmallD#method.on_gateway_payload).
+///
+/// Dropping a `ListenerHandle` leaves the listener registered. Use
+/// [`remove`](ListenerHandle#method.remove) to remove it, or
+/// [`remove_on_drop`](ListenerHandle#method.remove_on_drop) to tie its lifetime to a guard.
+/// It is safe to remove a listener from within a listener, including itself.
+#[derive(Clone, Debug)]
+pub struct ListenerHandle {
+    id: u64,
+    entries: Weak<Entries>,
+}
+
+impl ListenerHandle {
+    /// Removes the listener. It will not be called for any payloads received after this call.
+    pub fn remove(&self) {
+        if let Some(entries) = self.entries.upgrade() {
+            entries.lock().unwrap().remove(&self.id);
+        }
+    }
+
+    /// Converts this handle into a [`ListenerGuard`](ListenerGuard) that removes the listener
+    /// when it is dropped.
+    pub fn remove_on_drop(self) -> ListenerGuard {
+        ListenerGuard(Some(self))
+    }
+}
+
+/// Removes the listener it guards when dropped. Created via
+/// [`ListenerHandle::remove_on_drop`](ListenerHandle#method.remove_on_drop).
+#[derive(Debug)]
+pub struct ListenerGuard(Option<ListenerHandle>);
+
+impl ListenerGuard {
+    /// Releases the guard without removing the listener, returning its handle.
+    pub fn into_handle(mut self) -> ListenerHandle {
+        self.0.take().unwrap()
+    }
+}
+
+impl Drop for ListenerGuard {
+    fn drop(&mut self) {
+        if let Some(handle) = self.0.take() {
+            handle.remove();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smalld::SmallDBuilder;
+    use serde_json::json;
+    use std::sync::atomic::AtomicUsize;
+
+    fn smalld() -> SmallD {
+        SmallDBuilder::new().token("token").build().unwrap()
     }
 
-    pub fn notify(&mut self, smalld: &SmallD, payload: &Payload) {
-        for l in self.listeners.iter_mut() {
-            l(smalld, payload);
+    fn event(name: &str) -> Payload {
+        let mut payload = Payload::op(Op::Dispatch);
+        payload.t(name).d(json!({}));
+        payload
+    }
+
+    fn count_calls(count: &Arc<AtomicUsize>) -> impl Fn(&SmallD, &Payload) {
+        let count = count.clone();
+        move |_, _| {
+            count.fetch_add(1, Ordering::SeqCst);
         }
     }
+
+    #[test]
+    fn it_adds_listener_from_within_listener() {
+        let smalld = smalld();
+        let listeners = Arc::new(Listeners::new());
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let registry = listeners.clone();
+        let added = count.clone();
+        listeners.add_with_handle(None, move |handle| {
+            move |_: &SmallD, _: &Payload| {
+                handle.remove();
+                registry.add(count_calls(&added));
+            }
+        });
+
+        listeners.notify(&smalld, &event("MESSAGE_CREATE"));
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+
+        listeners.notify(&smalld, &event("MESSAGE_CREATE"));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn it_removes_listeners_from_within_listener() {
+        let smalld = smalld();
+        let listeners = Listeners::new();
+        let first = Arc::new(AtomicUsize::new(0));
+        let second = Arc::new(AtomicUsize::new(0));
+
+        let later: Arc<Mutex<Option<ListenerHandle>>> = Arc::new(Mutex::new(None));
+
+        let calls = count_calls(&first);
+        let to_remove = later.clone();
+        listeners.add_with_handle(None, move |handle| {
+            move |s: &SmallD, p: &Payload| {
+                calls(s, p);
+                handle.remove();
+                if let Some(later) = to_remove.lock().unwrap().as_ref() {
+                    later.remove();
+                }
+            }
+        });
+        *later.lock().unwrap() = Some(listeners.add(count_calls(&second)));
+
+        listeners.notify(&smalld, &event("MESSAGE_CREATE"));
+        listeners.notify(&smalld, &event("MESSAGE_CREATE"));
+
+        assert_eq!(first.load(Ordering::SeqCst), 1);
+        assert_eq!(second.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn it_only_notifies_listeners_for_the_event() {
+        let smalld = smalld();
+        let listeners = Listeners::new();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        listeners.add_with_handle(Some("GUILD_CREATE"), |_| count_calls(&count));
+
+        listeners.notify(&smalld, &event("MESSAGE_CREATE"));
+        listeners.notify(&smalld, &Payload::op(Op::HeartbeatAck));
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+
+        listeners.notify(&smalld, &event("GUILD_CREATE"));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn it_calls_once_listener_once() {
+        let smalld = smalld();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let calls = count.clone();
+        smalld.once("MESSAGE_CREATE", move |_, _| {
+            calls.fetch_add(1, Ordering::SeqCst);
+        });
+
+        smalld.dispatch(event("MESSAGE_CREATE"));
+        smalld.dispatch(event("MESSAGE_CREATE"));
+
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn it_continues_notifying_after_listener_panics() {
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let count = Arc::new(AtomicUsize::new(0));
+
+        let reported = errors.clone();
+        let smalld = SmallDBuilder::new()
+            .token("token")
+            .on_listener_error(move |err, event, _| {
+                reported
+                    .lock()
+                    .unwrap()
+                    .push((err.to_string(), event.map(str::to_string)));
+            })
+            .build()
+            .unwrap();
+
+        smalld.on_event("MESSAGE_CREATE", |_, _| panic!("listener failed"));
+        smalld.on_gateway_payload(count_calls(&count));
+
+        smalld.dispatch(event("MESSAGE_CREATE"));
+
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            *errors.lock().unwrap(),
+            vec![(
+                "listener failed".to_string(),
+                Some("MESSAGE_CREATE".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn it_stops_at_middleware_that_does_not_call_next() {
+        let smalld = smalld();
+        let count = Arc::new(AtomicUsize::new(0));
+
+        smalld.use_middleware(|s, p, next| {
+            if p.t.as_deref() != Some("TYPING_START") {
+                next.run(s, p);
+            }
+        });
+        smalld.on_gateway_payload(count_calls(&count));
+
+        smalld.dispatch(event("TYPING_START"));
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+
+        smalld.dispatch(event("MESSAGE_CREATE"));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
 }
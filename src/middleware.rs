@@ -0,0 +1,75 @@
+use crate::error::Error;
+use crate::listeners::{panic_message, Listeners};
+use crate::payload::Payload;
+use crate::smalld::SmallD;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, RwLock};
+
+pub type Middleware = dyn Fn(&SmallD, &Payload, Next) + Send + Sync + 'static;
+
+/// Ordered chain of middleware that is run before the listeners registered with
+/// [`SmallD`](crate::SmallD).
+pub(crate) struct MiddlewareChain {
+    middleware: RwLock<Vec<Arc<Middleware>>>,
+}
+
+impl MiddlewareChain {
+    pub fn new() -> MiddlewareChain {
+        MiddlewareChain {
+            middleware: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn add<F>(&self, f: F)
+    where
+        F: Fn(&SmallD, &Payload, Next) + Send + Sync + 'static,
+    {
+        self.middleware.write().unwrap().push(Arc::new(f));
+    }
+
+    /// Runs the middleware registered at the time of the call, followed by the listeners if
+    /// no middleware stops the chain.
+    pub fn run(&self, smalld: &SmallD, payload: &Payload, listeners: &Listeners) {
+        let middleware = self.middleware.read().unwrap().clone();
+
+        let next = Next {
+            middleware: &middleware,
+            listeners,
+        };
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| next.run(smalld, payload)));
+
+        if let Err(panic) = result {
+            let error = Error::ListenerPanic(panic_message(&panic));
+            smalld.report_listener_error(&error, payload);
+        }
+    }
+}
+
+/// The remainder of the middleware chain, as given to middleware registered via
+/// [`use_middleware`](crate::SmallD#method.use_middleware).
+///
+/// Calling [`run`](Next#method.run) continues with the next middleware, or the listeners if this
+/// is the last middleware. Middleware that does not call [`run`](Next#method.run) stops the
+/// payload from reaching any further middleware or listeners.
+pub struct Next<'a> {
+    middleware: &'a [Arc<Middleware>],
+    listeners: &'a Listeners,
+}
+
+impl<'a> Next<'a> {
+    /// Continues handling of the payload with the rest of the chain.
+    pub fn run(self, smalld: &SmallD, payload: &Payload) {
+        match self.middleware.split_first() {
+            Some((m, rest)) => m(
+                smalld,
+                payload,
+                Next {
+                    middleware: rest,
+                    listeners: self.listeners,
+                },
+            ),
+            None => self.listeners.notify(smalld, payload),
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Serde types for commonly used Discord objects.
+//!
+//! These types are only available with the `models` feature enabled.
+//!
+//! ```toml
+//! [dependencies]
+//! smalld_rust = { version = "*", features = ["models"] }
+//! ```
+//!
+//! The types are lenient: unknown fields are ignored and missing fields take their default value,
+//! so they keep working as Discord adds to its objects. Only the most used fields are included;
+//! for anything else keep using the json [`Value`](serde_json::Value). The types may be used
+//! with [`on_event_typed`](crate::SmallD#method.on_event_typed),
+//! [`get_as`](crate::SmallD#method.get_as), and [`post_as`](crate::SmallD#method.post_as), or
+//! converted to and from a [`Value`](serde_json::Value) via
+//! [`serde_json::to_value`](serde_json::to_value) and
+//! [`serde_json::from_value`](serde_json::from_value).
+//!
+//! ```no_run
+//! use smalld::models::Message;
+//! use smalld::SmallD;
+//!
+//! let smalld = SmallD::new().expect("Failed to initialize smalld");
+//!
+//! smalld.on_event_typed("MESSAGE_CREATE", |_smalld, message: Message| {
+//!   println!("{} said {}", message.author.username, message.content);
+//! });
+//! ```
+
+use crate::snowflake::Snowflake;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A Discord [user](https://discord.com/developers/docs/resources/user#user-object).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct User {
+    pub id: Snowflake,
+    pub username: String,
+    pub discriminator: String,
+    pub global_name: Option<String>,
+    pub avatar: Option<String>,
+    pub bot: bool,
+    pub system: bool,
+}
+
+/// A [guild member](https://discord.com/developers/docs/resources/guild#guild-member-object).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Member {
+    pub user: Option<User>,
+    pub nick: Option<String>,
+    pub avatar: Option<String>,
+    pub roles: Vec<Snowflake>,
+    pub joined_at: Option<String>,
+    pub deaf: bool,
+    pub mute: bool,
+    pub pending: bool,
+    pub permissions: Option<String>,
+}
+
+/// A [guild](https://discord.com/developers/docs/resources/guild#guild-object).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Guild {
+    pub id: Snowflake,
+    pub name: String,
+    pub icon: Option<String>,
+    pub owner_id: Option<Snowflake>,
+    pub roles: Vec<Role>,
+    pub emojis: Vec<Emoji>,
+    pub features: Vec<String>,
+    pub member_count: Option<u64>,
+    pub unavailable: bool,
+    pub channels: Vec<Channel>,
+    pub members: Vec<Member>,
+}
+
+/// A [channel](https://discord.com/developers/docs/resources/channel#channel-object).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Channel {
+    pub id: Snowflake,
+    #[serde(rename = "type")]
+    pub kind: u8,
+    pub guild_id: Option<Snowflake>,
+    pub position: Option<i64>,
+    pub name: Option<String>,
+    pub topic: Option<String>,
+    pub nsfw: bool,
+    pub last_message_id: Option<Snowflake>,
+    pub parent_id: Option<Snowflake>,
+    pub recipients: Vec<User>,
+}
+
+/// A [message](https://discord.com/developers/docs/resources/channel#message-object).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Message {
+    pub id: Snowflake,
+    pub channel_id: Snowflake,
+    pub guild_id: Option<Snowflake>,
+    pub author: User,
+    pub member: Option<Member>,
+    pub content: String,
+    pub timestamp: String,
+    pub edited_timestamp: Option<String>,
+    pub tts: bool,
+    pub mention_everyone: bool,
+    pub mentions: Vec<User>,
+    pub mention_roles: Vec<Snowflake>,
+    pub attachments: Vec<Attachment>,
+    pub embeds: Vec<Embed>,
+    pub pinned: bool,
+    pub webhook_id: Option<Snowflake>,
+    #[serde(rename = "type")]
+    pub kind: u8,
+    pub referenced_message: Option<Box<Message>>,
+}
+
+/// An [attachment](https://discord.com/developers/docs/resources/channel#attachment-object) of a
+/// message.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Attachment {
+    pub id: Snowflake,
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub size: u64,
+    pub url: String,
+    pub proxy_url: String,
+}
+
+/// A [role](https://discord.com/developers/docs/topics/permissions#role-object).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Role {
+    pub id: Snowflake,
+    pub name: String,
+    pub color: u32,
+    pub hoist: bool,
+    pub icon: Option<String>,
+    pub position: i64,
+    pub permissions: String,
+    pub managed: bool,
+    pub mentionable: bool,
+}
+
+/// An [emoji](https://discord.com/developers/docs/resources/emoji#emoji-object). Standard
+/// emoji have no id.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Emoji {
+    pub id: Option<Snowflake>,
+    pub name: Option<String>,
+    pub roles: Vec<Snowflake>,
+    pub user: Option<User>,
+    pub require_colons: bool,
+    pub managed: bool,
+    pub animated: bool,
+    pub available: bool,
+}
+
+/// An [embed](https://discord.com/developers/docs/resources/channel#embed-object). Fields that
+/// are not set are left out when serializing, so an `Embed` can be used when sending messages.
+///
+/// ```rust
+/// use smalld::models::{Embed, EmbedField};
+/// use serde_json::json;
+///
+/// let embed = Embed {
+///     title: Some("Pong".to_string()),
+///     fields: vec![EmbedField {
+///         name: "Latency".to_string(),
+///         value: "42ms".to_string(),
+///         inline: false,
+///     }],
+///     ..Embed::default()
+/// };
+///
+/// assert_eq!(
+///     serde_json::to_value(&embed).unwrap(),
+///     json!({"title": "Pong", "fields": [{"name": "Latency", "value": "42ms", "inline": false}]})
+/// );
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Embed {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub footer: Option<EmbedFooter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<EmbedMedia>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<EmbedMedia>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<EmbedAuthor>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<EmbedField>,
+}
+
+/// The footer of an [`Embed`](Embed).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmbedFooter {
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
+}
+
+/// An image or thumbnail of an [`Embed`](Embed).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmbedMedia {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+}
+
+/// The author of an [`Embed`](Embed).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmbedAuthor {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
+}
+
+/// A field of an [`Embed`](Embed).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+    pub inline: bool,
+}
+
+/// An [interaction](https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-object).
+/// The interaction `data` varies by interaction type so is left as json.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Interaction {
+    pub id: Snowflake,
+    pub application_id: Snowflake,
+    #[serde(rename = "type")]
+    pub kind: u8,
+    pub data: Option<Value>,
+    pub guild_id: Option<Snowflake>,
+    pub channel_id: Option<Snowflake>,
+    pub member: Option<Member>,
+    pub user: Option<User>,
+    pub token: String,
+    pub version: u8,
+    pub message: Option<Message>,
+    pub locale: Option<String>,
+    pub guild_locale: Option<String>,
+}
+
+/// The data of the [READY](https://discord.com/developers/docs/topics/gateway-events#ready)
+/// event. Guilds are unavailable at this point, so only have their `id` set.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReadyEvent {
+    pub v: u8,
+    pub user: User,
+    pub guilds: Vec<Guild>,
+    pub session_id: String,
+    pub resume_gateway_url: Option<String>,
+    pub shard: Option<Vec<u64>>,
+    pub application: Option<PartialApplication>,
+}
+
+/// The partial application included in a [`ReadyEvent`](ReadyEvent).
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PartialApplication {
+    pub id: Snowflake,
+    pub flags: Option<u64>,
+}
@@ -0,0 +1,219 @@
+use crate::error::Error;
+use serde_json::Value;
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A file to be uploaded with a request, for example as a message attachment. Used with
+/// [`post_multipart`](crate::SmallD#method.post_multipart) and
+/// [`patch_multipart`](crate::SmallD#method.patch_multipart).
+///
+/// The content type is guessed from the extension of the filename if not set explicitly.
+///
+/// ```rust
+/// use smalld::Upload;
+///
+/// let upload = Upload::from_bytes("hello.txt", "Hello, World!");
+/// assert_eq!(upload.get_content_type(), "text/plain");
+///
+/// let upload = Upload::from_bytes("data.bin", vec![1, 2, 3]).content_type("application/x-custom");
+/// assert_eq!(upload.get_content_type(), "application/x-custom");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Upload {
+    filename: String,
+    content_type: Option<String>,
+    data: Vec<u8>,
+}
+
+impl Upload {
+    pub fn from_bytes<S: Into<String>, B: Into<Vec<u8>>>(filename: S, data: B) -> Upload {
+        Upload {
+            filename: filename.into(),
+            content_type: None,
+            data: data.into(),
+        }
+    }
+
+    pub fn from_reader<S: Into<String>, R: Read>(
+        filename: S,
+        mut reader: R,
+    ) -> Result<Upload, Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Ok(Upload::from_bytes(filename, data))
+    }
+
+    pub fn content_type<S: Into<String>>(mut self, content_type: S) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    pub fn get_content_type(&self) -> &str {
+        self.content_type
+            .as_deref()
+            .unwrap_or_else(|| guess_content_type(&self.filename))
+    }
+}
+
+fn guess_content_type(filename: &str) -> &'static str {
+    let extension = filename.rsplit('.').next().unwrap_or("");
+
+    match extension.to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "txt" | "log" => "text/plain",
+        "json" => "application/json",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+/// A `multipart/form-data` body as expected by Discord for requests with
+/// [file uploads](https://discord.com/developers/docs/reference#uploading-files).
+pub(crate) struct Multipart {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+impl Multipart {
+    pub fn new(json: &Value, uploads: &[Upload]) -> Multipart {
+        let mut multipart = Multipart {
+            boundary: boundary(),
+            body: Vec::new(),
+        };
+
+        multipart.add_part(
+            "payload_json",
+            None,
+            "application/json",
+            json.to_string().as_bytes(),
+        );
+
+        for (n, upload) in uploads.iter().enumerate() {
+            multipart.add_part(
+                &format!("files[{}]", n),
+                Some(&upload.filename),
+                upload.get_content_type(),
+                &upload.data,
+            );
+        }
+
+        multipart
+            .body
+            .extend_from_slice(format!("--{}--\r\n", multipart.boundary).as_bytes());
+
+        multipart
+    }
+
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    fn add_part(&mut self, name: &str, filename: Option<&str>, content_type: &str, data: &[u8]) {
+        let disposition = match filename {
+            Some(f) => format!(
+                "form-data; name=\"{}\"; filename=\"{}\"",
+                name,
+                escape_quoted(f)
+            ),
+            None => format!("form-data; name=\"{}\"", name),
+        };
+
+        let headers = format!(
+            "--{}\r\nContent-Disposition: {}\r\nContent-Type: {}\r\n\r\n",
+            self.boundary, disposition, content_type
+        );
+
+        self.body.extend_from_slice(headers.as_bytes());
+        self.body.extend_from_slice(data);
+        self.body.extend_from_slice(b"\r\n");
+    }
+}
+
+fn escape_quoted(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(['\r', '\n'], " ")
+}
+
+fn boundary() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+
+    format!(
+        "smalld-{:x}-{:x}",
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_builds_multipart_body() {
+        let uploads = [
+            Upload::from_bytes("hello.txt", "Hello, World!"),
+            Upload::from_bytes("say \"hi\"\r\n.bin", vec![0, 1, 255]),
+        ];
+
+        let multipart = Multipart::new(&json!({"content": "files"}), &uploads);
+        let boundary = &multipart.boundary;
+
+        let mut expected = format!(
+            "--{b}\r\n\
+             Content-Disposition: form-data; name=\"payload_json\"\r\n\
+             Content-Type: application/json\r\n\
+             \r\n\
+             {{\"content\":\"files\"}}\r\n\
+             --{b}\r\n\
+             Content-Disposition: form-data; name=\"files[0]\"; filename=\"hello.txt\"\r\n\
+             Content-Type: text/plain\r\n\
+             \r\n\
+             Hello, World!\r\n\
+             --{b}\r\n\
+             Content-Disposition: form-data; name=\"files[1]\"; filename=\"say \\\"hi\\\"  .bin\"\r\n\
+             Content-Type: application/octet-stream\r\n\
+             \r\n",
+            b = boundary
+        )
+        .into_bytes();
+        expected.extend_from_slice(&[0, 1, 255]);
+        expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+        assert_eq!(multipart.body(), expected.as_slice());
+        assert_eq!(
+            multipart.content_type(),
+            format!("multipart/form-data; boundary={}", boundary)
+        );
+    }
+
+    #[test]
+    fn it_uses_a_new_boundary_for_each_body() {
+        let first = Multipart::new(&json!({}), &[]);
+        let second = Multipart::new(&json!({}), &[]);
+
+        assert_ne!(first.boundary, second.boundary);
+    }
+}
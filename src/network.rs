@@ -0,0 +1,213 @@
+use crate::error::Error;
+use native_tls::{Certificate, HandshakeError, TlsConnector};
+use percent_encoding::percent_decode_str;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+use tungstenite::client::AutoStream;
+use tungstenite::error::TlsError;
+use tungstenite::stream::Stream;
+use ureq::{Agent, AgentBuilder, Proxy};
+use url::Url;
+
+/// Limit on the size of a proxy's response to `CONNECT`, to avoid reading forever from a
+/// misbehaving proxy.
+const MAX_PROXY_RESPONSE: usize = 8192;
+
+/// Settings for the network connections made to Discord, as configured on
+/// [`SmallDBuilder`](crate::SmallDBuilder).
+#[derive(Clone, Debug)]
+pub(crate) struct NetworkConfig {
+    pub connect_timeout: Duration,
+    pub read_timeout: Option<Duration>,
+    pub write_timeout: Option<Duration>,
+    pub max_idle_connections: usize,
+    pub tls_connector: Arc<TlsConnector>,
+    pub proxy: Option<Url>,
+    agent_proxy: Option<Proxy>,
+}
+
+impl NetworkConfig {
+    pub fn new(
+        connect_timeout: Duration,
+        read_timeout: Option<Duration>,
+        write_timeout: Option<Duration>,
+        proxy: Option<&str>,
+        max_idle_connections: usize,
+        root_certificates: &[Vec<u8>],
+    ) -> Result<NetworkConfig, Error> {
+        let (proxy, agent_proxy) = match proxy.map(Self::parse_proxy).transpose()? {
+            Some((url, agent_proxy)) => (Some(url), Some(agent_proxy)),
+            None => (None, None),
+        };
+
+        let mut tls = TlsConnector::builder();
+
+        for pem in root_certificates {
+            let certificate = Certificate::from_pem(pem)
+                .map_err(|e| Error::ConfigurationError(format!("Bad root certificate: {}", e)))?;
+            tls.add_root_certificate(certificate);
+        }
+
+        let tls_connector = tls
+            .build()
+            .map_err(|e| Error::ConfigurationError(format!("Unable to configure TLS: {}", e)))?;
+
+        Ok(NetworkConfig {
+            connect_timeout,
+            read_timeout,
+            write_timeout,
+            proxy,
+            max_idle_connections,
+            tls_connector: Arc::new(tls_connector),
+            agent_proxy,
+        })
+    }
+
+    fn parse_proxy(s: &str) -> Result<(Url, Proxy), Error> {
+        let error = || Error::ConfigurationError(format!("Bad proxy: {}", s));
+
+        match Url::parse(s) {
+            Ok(url) if url.scheme() == "http" && url.host_str().is_some() => {
+                let agent_proxy = Proxy::new(s).map_err(|_e| error())?;
+                Ok((url, agent_proxy))
+            }
+            _ => Err(error()),
+        }
+    }
+
+    /// Builds a HTTP agent for Discord's resources api with these settings.
+    pub fn agent(&self) -> Agent {
+        let mut builder = AgentBuilder::new()
+            .timeout_connect(self.connect_timeout)
+            .max_idle_connections(self.max_idle_connections)
+            .tls_connector(self.tls_connector.clone());
+
+        if let Some(timeout) = self.read_timeout {
+            builder = builder.timeout_read(timeout);
+        }
+
+        if let Some(timeout) = self.write_timeout {
+            builder = builder.timeout_write(timeout);
+        }
+
+        if let Some(proxy) = &self.agent_proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+
+        builder.build()
+    }
+
+    /// Opens a stream for a websocket connection to `url`. The stream is tunneled through the
+    /// proxy if one is set, and uses TLS with the configured root certificates for `wss` urls.
+    pub fn open_stream(&self, url: &Url) -> Result<AutoStream, Error> {
+        let host = url
+            .host_str()
+            .ok_or_else(|| Error::IllegalArgumentError(format!("No host in url: {}", url)))?;
+
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| Error::IllegalArgumentError(format!("No port for url: {}", url)))?;
+
+        let stream = match &self.proxy {
+            Some(proxy) => self.connect_via_proxy(proxy, host, port)?,
+            None => self.connect_tcp(host, port)?,
+        };
+
+        match url.scheme() {
+            "ws" => Ok(Stream::Plain(stream)),
+            "wss" => match self.tls_connector.connect(host, stream) {
+                Ok(tls) => Ok(Stream::Tls(tls)),
+                Err(HandshakeError::Failure(err)) => {
+                    Err(tungstenite::Error::from(TlsError::Native(err)).into())
+                }
+                Err(HandshakeError::WouldBlock(_)) => {
+                    Err(Error::illegal_state("TLS handshake did not complete"))
+                }
+            },
+            scheme => Err(Error::IllegalArgumentError(format!(
+                "Unsupported scheme for websocket: {}",
+                scheme
+            ))),
+        }
+    }
+
+    /// Connects to the first reachable address of `host`. Reads and writes on the returned
+    /// stream time out after the connect timeout, to bound the time spent in handshakes.
+    fn connect_tcp(&self, host: &str, port: u16) -> Result<TcpStream, Error> {
+        let mut last_error = None;
+
+        for addr in (host, port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, self.connect_timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.connect_timeout))?;
+                    stream.set_write_timeout(Some(self.connect_timeout))?;
+                    return Ok(stream);
+                }
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        Err(last_error
+            .unwrap_or_else(|| {
+                io::Error::new(ErrorKind::NotFound, format!("No address for {}", host))
+            })
+            .into())
+    }
+
+    /// Opens a tunnel to `host` through a HTTP proxy using `CONNECT`.
+    fn connect_via_proxy(&self, proxy: &Url, host: &str, port: u16) -> Result<TcpStream, Error> {
+        let proxy_host = proxy.host_str().unwrap_or_default();
+        let mut stream =
+            self.connect_tcp(proxy_host, proxy.port_or_known_default().unwrap_or(80))?;
+
+        let mut request = format!(
+            "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n",
+            host = host,
+            port = port
+        );
+
+        if !proxy.username().is_empty() {
+            let credentials = format!(
+                "{}:{}",
+                percent_decode_str(proxy.username()).decode_utf8_lossy(),
+                percent_decode_str(proxy.password().unwrap_or_default()).decode_utf8_lossy()
+            );
+            request.push_str(&format!(
+                "Proxy-Authorization: Basic {}\r\n",
+                base64::encode(credentials)
+            ));
+        }
+
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes())?;
+
+        // Read byte by byte so that nothing after the proxy's response is consumed
+        let mut response = Vec::new();
+        let mut byte = [0u8; 1];
+
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= MAX_PROXY_RESPONSE || stream.read(&mut byte)? == 0 {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "Incomplete response from proxy",
+                )
+                .into());
+            }
+            response.push(byte[0]);
+        }
+
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or_default();
+
+        match status_line.split_whitespace().nth(1) {
+            Some(status) if status.starts_with('2') => Ok(stream),
+            _ => Err(io::Error::new(
+                ErrorKind::ConnectionRefused,
+                format!("Proxy refused connection: {}", status_line),
+            )
+            .into()),
+        }
+    }
+}
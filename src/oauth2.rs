@@ -0,0 +1,153 @@
+use crate::error::Error;
+use crate::http::Http;
+use crate::request::{Method, RequestBuilder};
+use crate::smalld::SmallDBuilder;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// An OAuth2 access token, as returned from Discord's
+/// [token endpoint](https://discord.com/developers/docs/topics/oauth2#authorization-code-grant-access-token-response).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessToken {
+    pub access_token: String,
+    pub token_type: String,
+    pub refresh_token: Option<String>,
+    pub scope: String,
+    pub expires_at: SystemTime,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    token_type: String,
+    expires_in: u64,
+    refresh_token: Option<String>,
+    #[serde(default)]
+    scope: String,
+}
+
+impl From<TokenResponse> for AccessToken {
+    fn from(rsp: TokenResponse) -> Self {
+        AccessToken {
+            access_token: rsp.access_token,
+            token_type: rsp.token_type,
+            refresh_token: rsp.refresh_token,
+            scope: rsp.scope,
+            expires_at: SystemTime::now() + Duration::from_secs(rsp.expires_in),
+        }
+    }
+}
+
+impl AccessToken {
+    /// Whether the token expires within the given duration from now.
+    pub fn expires_within(&self, duration: Duration) -> bool {
+        SystemTime::now() + duration >= self.expires_at
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_within(Duration::from_secs(0))
+    }
+}
+
+/// A client for Discord's [OAuth2](https://discord.com/developers/docs/topics/oauth2) token
+/// endpoint, for obtaining and refreshing [`AccessToken`s](AccessToken).
+///
+/// To act on behalf of a user with the token obtained, and have it refreshed automatically when
+/// it expires, pass both the client and token to
+/// [`SmallDBuilder::oauth2_token`](SmallDBuilder#method.oauth2_token).
+///
+/// ```no_run
+/// use smalld::{OAuth2Client, SmallDBuilder};
+///
+/// let client = OAuth2Client::new("1234", "client-secret")
+///   .expect("Failed to initialize client")
+///   .redirect_uri("https://example.com/callback");
+///
+/// let token = client
+///   .exchange_code("code-from-redirect")
+///   .expect("Failed to exchange code");
+///
+/// let smalld = SmallDBuilder::new()
+///   .oauth2_token(client, token)
+///   .build()
+///   .expect("Failed to initialize smalld");
+///
+/// let guilds = smalld.get("/users/@me/guilds").expect("Failed to fetch guilds");
+/// ```
+#[derive(Clone)]
+pub struct OAuth2Client {
+    http: Arc<Http>,
+    client_id: String,
+    client_secret: String,
+    redirect_uri: Option<String>,
+}
+
+impl OAuth2Client {
+    /// Equivalent to
+    /// [`SmallDBuilder::new().build_oauth2_client(client_id, client_secret)`](SmallDBuilder#method.build_oauth2_client).
+    pub fn new<A: Into<String>, B: Into<String>>(
+        client_id: A,
+        client_secret: B,
+    ) -> Result<OAuth2Client, Error> {
+        SmallDBuilder::new().build_oauth2_client(client_id, client_secret)
+    }
+
+    pub(crate) fn with_http<A: Into<String>, B: Into<String>>(
+        http: Arc<Http>,
+        client_id: A,
+        client_secret: B,
+    ) -> OAuth2Client {
+        OAuth2Client {
+            http,
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            redirect_uri: None,
+        }
+    }
+
+    /// Sets the redirect uri, which must match the one used in the authorization url.
+    pub fn redirect_uri<S: Into<String>>(mut self, redirect_uri: S) -> Self {
+        self.redirect_uri = Some(redirect_uri.into());
+        self
+    }
+
+    /// Exchanges the code received by the redirect uri for an access token.
+    pub fn exchange_code<S: Into<String>>(&self, code: S) -> Result<AccessToken, Error> {
+        let mut request = self.token_request("authorization_code").form("code", code);
+
+        if let Some(redirect_uri) = &self.redirect_uri {
+            request = request.form("redirect_uri", redirect_uri.as_str());
+        }
+
+        Self::send(request)
+    }
+
+    /// Exchanges a refresh token for a new access token.
+    pub fn refresh<S: Into<String>>(&self, refresh_token: S) -> Result<AccessToken, Error> {
+        Self::send(
+            self.token_request("refresh_token")
+                .form("refresh_token", refresh_token),
+        )
+    }
+
+    /// Obtains an access token for the application's owner with the given scopes.
+    pub fn client_credentials(&self, scopes: &[&str]) -> Result<AccessToken, Error> {
+        Self::send(
+            self.token_request("client_credentials")
+                .form("scope", scopes.join(" ")),
+        )
+    }
+
+    fn token_request(&self, grant_type: &str) -> RequestBuilder<'_> {
+        RequestBuilder::new(&self.http, Method::Post, "/oauth2/token")
+            .form("client_id", self.client_id.as_str())
+            .form("client_secret", self.client_secret.as_str())
+            .form("grant_type", grant_type)
+    }
+
+    fn send(request: RequestBuilder) -> Result<AccessToken, Error> {
+        let rsp: TokenResponse = serde_json::from_value(request.send()?)?;
+        Ok(rsp.into())
+    }
+}
@@ -0,0 +1,349 @@
+use crate::error::Error;
+use crate::http::QueryParameters;
+use crate::request::Method;
+use crate::response::Response;
+use crate::smalld::SmallD;
+use crate::snowflake::Snowflake;
+use log::warn;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::thread::sleep;
+use std::time::Duration;
+
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+
+/// Limit on the total time spent waiting to retry a rate limited page.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(10);
+
+/// Direction in which a [`Paginator`](Paginator) walks a list endpoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaginationKind {
+    /// Walks towards older items using the `before` parameter, starting from the newest.
+    Before,
+
+    /// Walks towards newer items using the `after` parameter, starting from the oldest.
+    After,
+}
+
+/// Iterator over all items of a list endpoint that supports cursor based pagination. Created
+/// via [`SmallD::paginate`](crate::SmallD#method.paginate) or one of the helpers for known
+/// endpoints, such as [`messages`](Paginator#method.messages).
+///
+/// Pages are fetched as the iterator advances, with the `before` or `after` parameter set to the
+/// id of the last item of the previous page. Iteration ends after a page with less than the page
+/// size items, or after the first error. A rate limited page is retried after the time given by
+/// Discord, up to 5 times and for at most 10 seconds in total.
+///
+/// ```no_run
+/// use smalld::{Paginator, SmallD};
+///
+/// let smalld = SmallD::new().expect("Failed to initialize smalld");
+///
+/// for message in Paginator::messages(&smalld, "1234").take(500) {
+///   println!("{:?}", message.expect("Error fetching messages").get("content"));
+/// }
+/// ```
+pub struct Paginator<'a> {
+    smalld: &'a SmallD,
+    path: String,
+    kind: PaginationKind,
+    page_size: u32,
+    parameters: QueryParameters,
+    items_pointer: String,
+    id_pointer: String,
+    cursor: Option<Snowflake>,
+    page: VecDeque<Value>,
+    done: bool,
+}
+
+impl<'a> Paginator<'a> {
+    pub(crate) fn new<S: Into<String>>(
+        smalld: &'a SmallD,
+        path: S,
+        kind: PaginationKind,
+        page_size: u32,
+    ) -> Self {
+        Paginator {
+            smalld,
+            path: path.into(),
+            kind,
+            page_size,
+            parameters: QueryParameters::new(),
+            items_pointer: String::new(),
+            id_pointer: "/id".to_string(),
+            // Without an `after` some endpoints, such as messages, return the newest items
+            cursor: match kind {
+                PaginationKind::Before => None,
+                PaginationKind::After => Some(Snowflake(0)),
+            },
+            page: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// All messages in a channel, newest first.
+    pub fn messages<S: AsRef<str>>(smalld: &'a SmallD, channel_id: S) -> Self {
+        let path = format!("/channels/{}/messages", channel_id.as_ref());
+        Paginator::new(smalld, path, PaginationKind::Before, 100)
+    }
+
+    /// All members of a guild. Requires the `GUILD_MEMBERS` privileged intent.
+    pub fn guild_members<S: AsRef<str>>(smalld: &'a SmallD, guild_id: S) -> Self {
+        let path = format!("/guilds/{}/members", guild_id.as_ref());
+        Paginator::new(smalld, path, PaginationKind::After, 1000).id_at("/user/id")
+    }
+
+    /// All bans of a guild.
+    pub fn bans<S: AsRef<str>>(smalld: &'a SmallD, guild_id: S) -> Self {
+        let path = format!("/guilds/{}/bans", guild_id.as_ref());
+        Paginator::new(smalld, path, PaginationKind::After, 1000).id_at("/user/id")
+    }
+
+    /// All entries of a guild's audit log, newest first.
+    pub fn audit_log<S: AsRef<str>>(smalld: &'a SmallD, guild_id: S) -> Self {
+        let path = format!("/guilds/{}/audit-logs", guild_id.as_ref());
+        Paginator::new(smalld, path, PaginationKind::Before, 100).items_at("/audit_log_entries")
+    }
+
+    /// All guilds of the current user.
+    pub fn current_user_guilds(smalld: &'a SmallD) -> Self {
+        Paginator::new(smalld, "/users/@me/guilds", PaginationKind::After, 200)
+    }
+
+    /// Adds a query parameter to be sent with every page request.
+    pub fn query<A, B>(mut self, key: A, value: B) -> Self
+    where
+        A: Into<String>,
+        B: Into<String>,
+    {
+        self.parameters = self.parameters.add(key, value);
+        self
+    }
+
+    /// Starts the pagination before or after the given id, rather than at the newest or oldest
+    /// item. Combine with [`Snowflake::from_timestamp`](Snowflake#method.from_timestamp) to
+    /// start at a point in time.
+    pub fn starting_at<S: Into<Snowflake>>(mut self, id: S) -> Self {
+        self.cursor = Some(id.into());
+        self
+    }
+
+    /// Sets where the items are in the response, as a
+    /// [JSON pointer](https://tools.ietf.org/html/rfc6901). By default the response itself is
+    /// expected to be the array of items.
+    pub fn items_at<S: Into<String>>(mut self, pointer: S) -> Self {
+        self.items_pointer = pointer.into();
+        self
+    }
+
+    /// Sets where the id is in each item, as a [JSON pointer](https://tools.ietf.org/html/rfc6901).
+    /// Defaults to `/id`.
+    pub fn id_at<S: Into<String>>(mut self, pointer: S) -> Self {
+        self.id_pointer = pointer.into();
+        self
+    }
+
+    fn fetch_page(&mut self) -> Result<(), Error> {
+        let mut parameters = self
+            .parameters
+            .clone()
+            .add("limit", self.page_size.to_string());
+
+        if let Some((key, cursor)) = self.cursor_parameter() {
+            parameters = parameters.add(key, cursor);
+        }
+
+        let mut retries = 0;
+        let mut waited = Duration::from_secs(0);
+
+        loop {
+            let rsp = self
+                .smalld
+                .request(Method::Get, self.path.as_str())
+                .parameters(parameters.clone())
+                .send_raw()?;
+
+            if rsp.status() == 429 && retries < MAX_RATE_LIMIT_RETRIES {
+                let retry_after = retry_after(&rsp);
+
+                if waited + retry_after <= MAX_RATE_LIMIT_WAIT {
+                    warn!(
+                        "Rate limited fetching {}, retrying in {:?}",
+                        self.path, retry_after
+                    );
+
+                    retries += 1;
+                    waited += retry_after;
+                    sleep(retry_after);
+                    continue;
+                }
+            }
+
+            return self.add_page(rsp.error_for_status()?.json()?);
+        }
+    }
+
+    fn cursor_parameter(&self) -> Option<(&'static str, Snowflake)> {
+        let key = match self.kind {
+            PaginationKind::Before => "before",
+            PaginationKind::After => "after",
+        };
+
+        self.cursor.map(|cursor| (key, cursor))
+    }
+
+    /// Adds the items of a page to be iterated over, and advances the cursor past them.
+    fn add_page(&mut self, mut rsp: Value) -> Result<(), Error> {
+        let items = match rsp.pointer_mut(&self.items_pointer).map(Value::take) {
+            Some(Value::Array(items)) => items,
+            _ => {
+                return Err(Error::illegal_state(format!(
+                    "No items found at {} in response from {}",
+                    self.items_pointer, self.path
+                )))
+            }
+        };
+
+        let ids = items
+            .iter()
+            .map(|item| self.id_of(item))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.cursor = match self.kind {
+            PaginationKind::Before => ids.into_iter().min(),
+            PaginationKind::After => ids.into_iter().max(),
+        }
+        .or(self.cursor);
+
+        self.done = items.len() < self.page_size as usize;
+        self.page.extend(items);
+
+        Ok(())
+    }
+
+    fn id_of(&self, item: &Value) -> Result<Snowflake, Error> {
+        item.pointer(&self.id_pointer)
+            .and_then(|id| Snowflake::deserialize(id).ok())
+            .ok_or_else(|| {
+                Error::illegal_state(format!(
+                    "No id found at {} in item from {}",
+                    self.id_pointer, self.path
+                ))
+            })
+    }
+}
+
+/// How long to wait before retrying a rate limited request. Taken from the `retry_after` in the
+/// body if present, as it is more precise than the `Retry-After` header.
+fn retry_after(rsp: &Response) -> Duration {
+    let header = rsp
+        .header("Retry-After")
+        .and_then(|h| h.parse::<f64>().ok());
+
+    let body = rsp
+        .json()
+        .ok()
+        .and_then(|json| json.get("retry_after").and_then(Value::as_f64));
+
+    Duration::from_secs_f64(body.or(header).unwrap_or(1.0).max(0.0))
+}
+
+impl<'a> Iterator for Paginator<'a> {
+    type Item = Result<Value, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.page.is_empty() && !self.done {
+            if let Err(err) = self.fetch_page() {
+                self.done = true;
+                return Some(Err(err));
+            }
+        }
+
+        self.page.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smalld::SmallDBuilder;
+    use serde_json::json;
+
+    fn smalld() -> SmallD {
+        SmallDBuilder::new().token("token").build().unwrap()
+    }
+
+    fn drain(paginator: &mut Paginator) -> Vec<Value> {
+        paginator.page.drain(..).collect()
+    }
+
+    #[test]
+    fn it_starts_after_from_the_oldest() {
+        let smalld = smalld();
+
+        let after = smalld.paginate("/guilds/1/members", PaginationKind::After, 2);
+        assert_eq!(after.cursor_parameter(), Some(("after", Snowflake(0))));
+
+        let before = smalld.paginate("/channels/1/messages", PaginationKind::Before, 2);
+        assert_eq!(before.cursor_parameter(), None);
+
+        let started = before.starting_at(Snowflake(10));
+        assert_eq!(started.cursor_parameter(), Some(("before", Snowflake(10))));
+    }
+
+    #[test]
+    fn it_advances_cursor_past_each_page() {
+        let smalld = smalld();
+
+        let mut before = smalld.paginate("/channels/1/messages", PaginationKind::Before, 2);
+        before.add_page(json!([{"id": "9"}, {"id": "7"}])).unwrap();
+        assert_eq!(before.cursor_parameter(), Some(("before", Snowflake(7))));
+        assert!(!before.done);
+
+        let mut after = smalld.paginate("/guilds/1/bans", PaginationKind::After, 2);
+        after.add_page(json!([{"id": "3"}, {"id": "5"}])).unwrap();
+        assert_eq!(after.cursor_parameter(), Some(("after", Snowflake(5))));
+        assert_eq!(
+            drain(&mut after),
+            vec![json!({"id": "3"}), json!({"id": "5"})]
+        );
+    }
+
+    #[test]
+    fn it_ends_after_a_page_smaller_than_the_page_size() {
+        let smalld = smalld();
+        let mut paginator = smalld.paginate("/guilds/1/bans", PaginationKind::After, 2);
+
+        paginator.add_page(json!([{"id": "3"}])).unwrap();
+        assert!(paginator.done);
+        assert_eq!(paginator.next().unwrap().unwrap(), json!({"id": "3"}));
+        assert!(paginator.next().is_none());
+
+        let mut empty = smalld.paginate("/guilds/1/bans", PaginationKind::After, 2);
+        empty.add_page(json!([])).unwrap();
+        assert!(empty.done);
+        assert_eq!(empty.cursor_parameter(), Some(("after", Snowflake(0))));
+    }
+
+    #[test]
+    fn it_finds_items_and_ids_at_pointers() {
+        let smalld = smalld();
+        let mut paginator = smalld
+            .paginate("/guilds/1/audit-logs", PaginationKind::Before, 2)
+            .items_at("/entries")
+            .id_at("/user/id");
+
+        paginator
+            .add_page(json!({"entries": [{"user": {"id": "4"}}, {"user": {"id": "2"}}]}))
+            .unwrap();
+
+        assert_eq!(paginator.cursor_parameter(), Some(("before", Snowflake(2))));
+        assert_eq!(drain(&mut paginator).len(), 2);
+
+        assert!(paginator.add_page(json!({"other": []})).is_err());
+        assert!(paginator
+            .add_page(json!({"entries": [{"id": "1"}]}))
+            .is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::error::Error;
+use crate::http::{Http, QueryParameters, RequestOptions};
+use crate::multipart::Upload;
+use crate::response::Response;
+use serde_json::Value;
+use std::time::Duration;
+
+/// HTTP method of a request made via [`SmallD::request`](crate::SmallD#method.request).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+    Head,
+    Options,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Patch => "PATCH",
+            Method::Delete => "DELETE",
+            Method::Head => "HEAD",
+            Method::Options => "OPTIONS",
+        }
+    }
+}
+
+pub(crate) enum Body {
+    None,
+    Json(Value),
+    Multipart(Value, Vec<Upload>),
+    Form(Vec<(String, String)>),
+}
+
+/// Builder for a request to Discord's resources api. Created via
+/// [`SmallD::request`](crate::SmallD#method.request).
+///
+/// ```no_run
+/// use serde_json::json;
+/// use smalld::{Method, SmallD};
+///
+/// let smalld = SmallD::new().expect("Failed to initialize smalld");
+///
+/// smalld
+///   .request(Method::Post, "/channels/1234/messages/bulk-delete")
+///   .json(json!({"messages": ["5678", "9012"]}))
+///   .reason("Cleaning up spam")
+///   .send();
+/// ```
+pub struct RequestBuilder<'a> {
+    http: &'a Http,
+    method: Method,
+    path: String,
+    parameters: QueryParameters,
+    body: Body,
+    options: RequestOptions,
+}
+
+impl<'a> RequestBuilder<'a> {
+    pub(crate) fn new<S: Into<String>>(http: &'a Http, method: Method, path: S) -> Self {
+        RequestBuilder {
+            http,
+            method,
+            path: path.into(),
+            parameters: QueryParameters::new(),
+            body: Body::None,
+            options: RequestOptions::new(),
+        }
+    }
+
+    /// Adds a query parameter to the url.
+    pub fn query<A, B>(mut self, key: A, value: B) -> Self
+    where
+        A: Into<String>,
+        B: Into<String>,
+    {
+        self.parameters = self.parameters.add(key, value);
+        self
+    }
+
+    /// Replaces all query parameters with `parameters`.
+    pub fn parameters(mut self, parameters: QueryParameters) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// Sets a json body for the request.
+    pub fn json(mut self, json: Value) -> Self {
+        self.body = Body::Json(json);
+        self
+    }
+
+    /// Adds a field to an `application/x-www-form-urlencoded` body for the request, replacing
+    /// any json or multipart body.
+    pub fn form<A, B>(mut self, key: A, value: B) -> Self
+    where
+        A: Into<String>,
+        B: Into<String>,
+    {
+        match &mut self.body {
+            Body::Form(fields) => fields.push((key.into(), value.into())),
+            _ => self.body = Body::Form(vec![(key.into(), value.into())]),
+        }
+        self
+    }
+
+    /// Sets a `multipart/form-data` body for the request. See
+    /// [`post_multipart`](crate::SmallD#method.post_multipart).
+    pub fn multipart(mut self, json: Value, uploads: Vec<Upload>) -> Self {
+        self.body = Body::Multipart(json, uploads);
+        self
+    }
+
+    /// Adds a header to the request. See [`RequestOptions::header`](RequestOptions#method.header).
+    pub fn header<A, B>(mut self, name: A, value: B) -> Self
+    where
+        A: Into<String>,
+        B: Into<String>,
+    {
+        self.options = self.options.header(name, value);
+        self
+    }
+
+    /// Sets the reason to be shown in the audit log. See
+    /// [`RequestOptions::reason`](RequestOptions#method.reason).
+    pub fn reason<S: Into<String>>(mut self, reason: S) -> Self {
+        self.options = self.options.reason(reason);
+        self
+    }
+
+    /// Sets the timeout for the request. See
+    /// [`RequestOptions::timeout`](RequestOptions#method.timeout).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options = self.options.timeout(timeout);
+        self
+    }
+
+    /// Replaces all options with `options`.
+    pub fn options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sends the request, returning the json response. See
+    /// [`Response::json`](Response#method.json) for how responses without a body are handled.
+    pub fn send(self) -> Result<Value, Error> {
+        self.send_raw()?.error_for_status()?.json()
+    }
+
+    /// Sends the request, returning the full [`Response`](Response) including status and
+    /// headers. Unlike [`send`](#method.send), error responses, such as a 429 with its
+    /// rate limit headers, are returned rather than resulting in an error.
+    pub fn send_raw(self) -> Result<Response, Error> {
+        self.http.send(
+            self.method,
+            &self.path,
+            self.parameters,
+            self.body,
+            &self.options,
+        )
+    }
+}
@@ -0,0 +1,213 @@
+use crate::discord_error::flatten_errors;
+use crate::error::Error;
+use serde_json::{json, Value};
+use std::io::Read;
+
+/// A response from Discord's resources api, as returned by
+/// [`RequestBuilder::send_raw`](crate::RequestBuilder#method.send_raw). Gives access to the
+/// status, headers, and body of the response, whatever its status. Use
+/// [`error_for_status`](Response#method.error_for_status) to turn error responses into an
+/// [`Error`](Error).
+///
+/// ```no_run
+/// use smalld::{Method, SmallD};
+///
+/// let smalld = SmallD::new().expect("Failed to initialize smalld");
+///
+/// let rsp = smalld
+///   .request(Method::Get, "/users/@me")
+///   .send_raw()
+///   .expect("Request failed");
+///
+/// println!("Requests remaining: {:?}", rsp.header("X-RateLimit-Remaining"));
+/// println!("User: {}", rsp.json().expect("Bad json"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Response {
+    status: u16,
+    status_text: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub(crate) fn from_ureq(response: ureq::Response) -> Result<Response, Error> {
+        let status = response.status();
+        let status_text = response.status_text().to_string();
+
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .flat_map(|name| {
+                response
+                    .all(&name)
+                    .into_iter()
+                    .map(|value| (name.clone(), value.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let mut body = Vec::new();
+        response.into_reader().read_to_end(&mut body)?;
+
+        Ok(Response {
+            status,
+            status_text,
+            headers,
+            body,
+        })
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Whether the status is 2xx.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Returns the response if it [is a success](Response#method.is_success), otherwise an
+    /// error. The error is an [`Error::DiscordApi`](Error::DiscordApi) if the body is json with
+    /// an error code, otherwise an [`Error::HttpError`](Error::HttpError).
+    pub fn error_for_status(self) -> Result<Response, Error> {
+        if self.is_success() {
+            return Ok(self);
+        }
+
+        let json: Value = serde_json::from_slice(&self.body).unwrap_or(Value::Null);
+
+        if let (Some(code), Some(message)) = (
+            json.get("code").and_then(Value::as_u64),
+            json.get("message").and_then(Value::as_str),
+        ) {
+            return Err(Error::DiscordApi {
+                status: self.status,
+                code: code.into(),
+                message: message.to_string(),
+                errors: json.get("errors").map(flatten_errors).unwrap_or_default(),
+            });
+        }
+
+        let body = String::from_utf8_lossy(&self.body);
+
+        Err(
+            match ureq::Response::new(self.status, &self.status_text, &body) {
+                Ok(response) => ureq::Error::Status(self.status, response).into(),
+                Err(err) => err.into(),
+            },
+        )
+    }
+
+    /// The value of the first header with the given name. Header names are case insensitive.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// All headers of the response, as name and value pairs.
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+
+    /// The body of the response as json. A 204 response is an empty json object, as it is for
+    /// [`send`](crate::RequestBuilder#method.send), and any other response with an empty body is
+    /// json `null`.
+    pub fn json(&self) -> Result<Value, Error> {
+        if self.status == 204 {
+            Ok(json!({}))
+        } else if self.body.iter().all(u8::is_ascii_whitespace) {
+            Ok(Value::Null)
+        } else {
+            Ok(serde_json::from_slice(&self.body)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldError, JsonErrorCode};
+
+    fn response(status: u16, body: &str) -> Response {
+        Response {
+            status,
+            status_text: "Status".to_string(),
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn it_returns_successful_response() {
+        let rsp = response(200, r#"{"id":"1"}"#).error_for_status().unwrap();
+
+        assert_eq!(rsp.status(), 200);
+    }
+
+    #[test]
+    fn it_builds_discord_api_error_from_json_body() {
+        let body = r#"{
+            "code": 50035,
+            "message": "Invalid Form Body",
+            "errors": {
+                "embeds": { "0": { "title": { "_errors": [
+                    { "code": "BASE_TYPE_REQUIRED", "message": "This field is required" }
+                ] } } }
+            }
+        }"#;
+
+        match response(400, body).error_for_status() {
+            Err(Error::DiscordApi {
+                status,
+                code,
+                message,
+                errors,
+            }) => {
+                assert_eq!(status, 400);
+                assert_eq!(code, JsonErrorCode::InvalidFormBody);
+                assert_eq!(message, "Invalid Form Body");
+                assert_eq!(
+                    errors,
+                    vec![FieldError {
+                        path: "embeds.0.title".to_string(),
+                        code: "BASE_TYPE_REQUIRED".to_string(),
+                        message: "This field is required".to_string(),
+                    }]
+                );
+            }
+            other => panic!("Expected DiscordApi error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_builds_discord_api_error_without_field_errors() {
+        match response(403, r#"{"code": 50013, "message": "Missing Permissions"}"#)
+            .error_for_status()
+        {
+            Err(Error::DiscordApi { code, errors, .. }) => {
+                assert_eq!(code, JsonErrorCode::MissingPermissions);
+                assert!(errors.is_empty());
+            }
+            other => panic!("Expected DiscordApi error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_returns_http_error_for_non_json_body() {
+        match response(502, "Bad Gateway").error_for_status() {
+            Err(Error::HttpError(_)) => (),
+            other => panic!("Expected HttpError, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Milliseconds since the unix epoch of the first second of 2015, the Discord epoch.
+const DISCORD_EPOCH: u64 = 1_420_070_400_000;
+
+/// A Discord [snowflake](https://discord.com/developers/docs/reference#snowflakes) id.
+///
+/// Snowflakes are serialized as strings, as Discord does, and may be deserialized from either
+/// strings or integers.
+///
+/// ```rust
+/// use smalld::Snowflake;
+///
+/// let id: Snowflake = serde_json::from_str("\"175928847299117063\"").unwrap();
+/// assert_eq!(id, Snowflake::from(175928847299117063));
+/// assert_eq!(serde_json::to_string(&id).unwrap(), "\"175928847299117063\"");
+/// ```
+///
+/// Snowflakes are ordered by the time they were created, and can be converted into a `String`
+/// for use in paths or with [`QueryParameters`](crate::QueryParameters).
+///
+/// ```rust
+/// use smalld::{QueryParameters, Snowflake};
+/// use std::time::{Duration, SystemTime};
+///
+/// let an_hour_ago = Snowflake::from_timestamp(SystemTime::now() - Duration::from_secs(3600));
+/// let params = QueryParameters::new().add("after", an_hour_ago);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Snowflake(pub u64);
+
+impl Snowflake {
+    /// Creates the smallest snowflake for the given time. This is useful as a `before` or `after`
+    /// bound when paginating. Times before the Discord epoch give a snowflake of 0.
+    ///
+    /// ```rust
+    /// use smalld::Snowflake;
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// let time = UNIX_EPOCH + Duration::from_millis(1462015105796);
+    /// assert_eq!(Snowflake::from_timestamp(time), Snowflake::from(175928847298985984));
+    /// assert_eq!(Snowflake::from_timestamp(time).timestamp(), time);
+    /// ```
+    pub fn from_timestamp(time: SystemTime) -> Snowflake {
+        let ms = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        Snowflake(ms.saturating_sub(DISCORD_EPOCH) << 22)
+    }
+
+    /// The time at which this snowflake was created.
+    ///
+    /// ```rust
+    /// use smalld::Snowflake;
+    /// use std::time::{Duration, UNIX_EPOCH};
+    ///
+    /// assert_eq!(
+    ///     Snowflake::from(175928847299117063).timestamp(),
+    ///     UNIX_EPOCH + Duration::from_millis(1462015105796)
+    /// );
+    /// ```
+    pub fn timestamp(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis((self.0 >> 22) + DISCORD_EPOCH)
+    }
+
+    /// The id of the internal worker that created this snowflake.
+    ///
+    /// ```rust
+    /// use smalld::Snowflake;
+    ///
+    /// let id = Snowflake::from(175928847299117063);
+    /// assert_eq!(id.worker_id(), 1);
+    /// assert_eq!(id.process_id(), 0);
+    /// assert_eq!(id.increment(), 7);
+    /// ```
+    pub fn worker_id(&self) -> u8 {
+        ((self.0 & 0x3E_0000) >> 17) as u8
+    }
+
+    /// The id of the internal process that created this snowflake.
+    pub fn process_id(&self) -> u8 {
+        ((self.0 & 0x1_F000) >> 12) as u8
+    }
+
+    /// Incremented for every id generated on the process that created this snowflake.
+    pub fn increment(&self) -> u16 {
+        (self.0 & 0xFFF) as u16
+    }
+}
+
+impl From<u64> for Snowflake {
+    fn from(id: u64) -> Self {
+        Snowflake(id)
+    }
+}
+
+impl From<Snowflake> for u64 {
+    fn from(id: Snowflake) -> Self {
+        id.0
+    }
+}
+
+impl From<Snowflake> for String {
+    fn from(id: Snowflake) -> Self {
+        id.to_string()
+    }
+}
+
+impl FromStr for Snowflake {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Snowflake)
+    }
+}
+
+impl fmt::Display for Snowflake {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Serialize for Snowflake {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'a> Deserialize<'a> for Snowflake {
+    fn deserialize<D>(deserializer: D) -> Result<Snowflake, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        deserializer.deserialize_any(SnowflakeVisitor)
+    }
+}
+
+struct SnowflakeVisitor;
+
+impl<'a> Visitor<'a> for SnowflakeVisitor {
+    type Value = Snowflake;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a snowflake as a string or integer")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Snowflake, E> {
+        Ok(Snowflake(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Snowflake, E> {
+        u64::try_from(v)
+            .map(Snowflake)
+            .map_err(|_e| E::custom(format!("Negative snowflake: {}", v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Snowflake, E> {
+        v.parse()
+            .map_err(|_e| E::custom(format!("Bad snowflake: {}", v)))
+    }
+}
@@ -0,0 +1,181 @@
+use crate::error::Error;
+use crate::http::Http;
+use crate::multipart::Upload;
+use crate::request::{Method, RequestBuilder};
+use crate::smalld::SmallDBuilder;
+use crate::snowflake::Snowflake;
+use serde_json::Value;
+use std::sync::Arc;
+use url::Url;
+
+/// A client for a single [webhook](https://discord.com/developers/docs/resources/webhook),
+/// authenticated by the webhook's token rather than a bot token. No `Authorization` header is
+/// sent with its requests.
+///
+/// A `Webhook` can be created from the webhook's url, or via
+/// [`SmallDBuilder::build_webhook`](SmallDBuilder#method.build_webhook) to apply settings such as
+/// a proxy, or via [`SmallD::webhook`](crate::SmallD#method.webhook) to share the connections of
+/// an existing `SmallD`.
+///
+/// ```no_run
+/// use serde_json::json;
+/// use smalld::Webhook;
+///
+/// let webhook = Webhook::from_url("https://discord.com/api/webhooks/1234/abcd")
+///   .expect("Bad webhook url");
+///
+/// let message = webhook
+///   .execute(json!({"content": "Deploy finished"}))
+///   .wait(true)
+///   .send()
+///   .expect("Failed to execute webhook");
+///
+/// webhook
+///   .edit_message(message["id"].as_str().unwrap(), json!({"content": "Deploy rolled back"}))
+///   .expect("Failed to edit message");
+/// ```
+#[derive(Clone)]
+pub struct Webhook {
+    http: Arc<Http>,
+    id: Snowflake,
+    token: String,
+}
+
+impl Webhook {
+    pub(crate) fn new<S: Into<String>>(http: Arc<Http>, id: Snowflake, token: S) -> Webhook {
+        Webhook {
+            http,
+            id,
+            token: token.into(),
+        }
+    }
+
+    /// Equivalent to
+    /// [`SmallDBuilder::new().build_webhook(url)`](SmallDBuilder#method.build_webhook).
+    pub fn from_url<S: AsRef<str>>(url: S) -> Result<Webhook, Error> {
+        SmallDBuilder::new().build_webhook(url)
+    }
+
+    /// Parses the id and token from a url of the form
+    /// `https://discord.com/api/webhooks/{id}/{token}`.
+    pub(crate) fn parse_url(s: &str) -> Result<(Snowflake, String), Error> {
+        let error = || Error::IllegalArgumentError(format!("Bad webhook url: {}", s));
+
+        let url = Url::parse(s).map_err(|_e| error())?;
+        let mut segments = url
+            .path_segments()
+            .ok_or_else(error)?
+            .skip_while(|segment| *segment != "webhooks")
+            .skip(1);
+
+        match (segments.next(), segments.next()) {
+            (Some(id), Some(token)) if !token.is_empty() => {
+                Ok((id.parse().map_err(|_e| error())?, token.to_string()))
+            }
+            _ => Err(error()),
+        }
+    }
+
+    pub fn id(&self) -> Snowflake {
+        self.id
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Starts building a request to a path relative to the webhook, such as
+    /// `/messages/@original`. All other methods are shorthands for this.
+    pub fn request<S: AsRef<str>>(&self, method: Method, path: S) -> RequestBuilder<'_> {
+        let path = format!("/webhooks/{}/{}{}", self.id, self.token, path.as_ref());
+        RequestBuilder::new(&self.http, method, path)
+    }
+
+    /// Starts building an execution of the webhook, posting a message with the given json.
+    pub fn execute(&self, json: Value) -> WebhookExecution<'_> {
+        WebhookExecution {
+            webhook: self,
+            json,
+            wait: false,
+            thread_id: None,
+            uploads: Vec::new(),
+        }
+    }
+
+    pub fn get_message<S: AsRef<str>>(&self, message_id: S) -> Result<Value, Error> {
+        self.request(Method::Get, format!("/messages/{}", message_id.as_ref()))
+            .send()
+    }
+
+    pub fn edit_message<S: AsRef<str>>(&self, message_id: S, json: Value) -> Result<Value, Error> {
+        self.request(Method::Patch, format!("/messages/{}", message_id.as_ref()))
+            .json(json)
+            .send()
+    }
+
+    /// Same as [`edit_message`](Webhook#method.edit_message), but also uploading files. See
+    /// [`SmallD::post_multipart`](crate::SmallD#method.post_multipart).
+    pub fn edit_message_multipart<S: AsRef<str>>(
+        &self,
+        message_id: S,
+        json: Value,
+        uploads: Vec<Upload>,
+    ) -> Result<Value, Error> {
+        self.request(Method::Patch, format!("/messages/{}", message_id.as_ref()))
+            .multipart(json, uploads)
+            .send()
+    }
+
+    pub fn delete_message<S: AsRef<str>>(&self, message_id: S) -> Result<Value, Error> {
+        self.request(Method::Delete, format!("/messages/{}", message_id.as_ref()))
+            .send()
+    }
+}
+
+/// Builder for an execution of a [`Webhook`](Webhook). Created via
+/// [`Webhook::execute`](Webhook#method.execute).
+pub struct WebhookExecution<'a> {
+    webhook: &'a Webhook,
+    json: Value,
+    wait: bool,
+    thread_id: Option<String>,
+    uploads: Vec<Upload>,
+}
+
+impl<'a> WebhookExecution<'a> {
+    /// Sets whether to wait for the message to be created. If so the created message is
+    /// returned, otherwise an empty json object is returned.
+    pub fn wait(mut self, wait: bool) -> Self {
+        self.wait = wait;
+        self
+    }
+
+    /// Sends the message to the given thread in the webhook's channel.
+    pub fn thread_id<S: Into<String>>(mut self, thread_id: S) -> Self {
+        self.thread_id = Some(thread_id.into());
+        self
+    }
+
+    /// Adds a file to be uploaded with the message.
+    pub fn upload(mut self, upload: Upload) -> Self {
+        self.uploads.push(upload);
+        self
+    }
+
+    pub fn send(self) -> Result<Value, Error> {
+        let mut request = self
+            .webhook
+            .request(Method::Post, "")
+            .query("wait", self.wait.to_string());
+
+        if let Some(thread_id) = self.thread_id {
+            request = request.query("thread_id", thread_id);
+        }
+
+        if self.uploads.is_empty() {
+            request.json(self.json).send()
+        } else {
+            request.multipart(self.json, self.uploads).send()
+        }
+    }
+}
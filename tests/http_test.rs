@@ -1,5 +1,10 @@
+use serde::Deserialize;
 use serde_json::json;
-use smalld::{Error, QueryParameters, SmallD, SmallDBuilder};
+use smalld::{
+    Error, Method, QueryParameters, RequestOptions, SmallD, SmallDBuilder, Snowflake, TokenType,
+    Upload, Webhook,
+};
+use std::time::Duration;
 
 const DUMMY_TOKEN: &str = "DuMmY.ToKeN";
 const HTTP_BIN: &str = "http://httpbin.org";
@@ -50,6 +55,32 @@ fn it_makes_post_request() {
     assert_eq!(rsp["json"], json);
 }
 
+#[derive(Deserialize)]
+struct HttpBinResponse {
+    url: String,
+    json: Option<serde_json::Value>,
+}
+
+#[test]
+fn it_makes_typed_get_request() {
+    let rsp: HttpBinResponse = subject().get_as("/get").unwrap();
+    assert_strlike_eq(rsp.url, http_bin("/get"));
+}
+
+#[test]
+fn it_makes_typed_post_request() {
+    let json = json!({"foo": "bar"});
+    let rsp: HttpBinResponse = subject().post_as("/post", json!({"foo": "bar"})).unwrap();
+    assert_strlike_eq(rsp.url, http_bin("/post"));
+    assert_eq!(rsp.json, Some(json));
+}
+
+#[test]
+fn it_errors_on_typed_request_with_unexpected_response() {
+    let rsp: Result<HttpBinResponse, Error> = subject().get_as("/uuid");
+    assert!(matches!(rsp, Err(Error::JsonError(_))));
+}
+
 #[test]
 fn it_makes_put_request() {
     let json = json!({"foo": "bar"});
@@ -66,12 +97,66 @@ fn it_makes_patch_request() {
     assert_eq!(rsp["json"], json);
 }
 
+#[test]
+fn it_makes_multipart_post_request() {
+    let json = json!({"content": "file"});
+    let rsp = subject()
+        .post_multipart(
+            "/post",
+            json.clone(),
+            vec![
+                Upload::from_bytes("a.txt", "file a"),
+                Upload::from_bytes("b.txt", "file b"),
+            ],
+        )
+        .unwrap();
+    assert_strlike_eq(
+        rsp["form"]["payload_json"].as_str().unwrap(),
+        json.to_string(),
+    );
+    assert_strlike_eq(rsp["files"]["files[0]"].as_str().unwrap(), "file a");
+    assert_strlike_eq(rsp["files"]["files[1]"].as_str().unwrap(), "file b");
+}
+
+#[test]
+fn it_makes_multipart_patch_request() {
+    let rsp = subject()
+        .patch_multipart(
+            "/patch",
+            json!({}),
+            vec![Upload::from_bytes("a.txt", "file a")],
+        )
+        .unwrap();
+    assert_strlike_eq(rsp["files"]["files[0]"].as_str().unwrap(), "file a");
+}
+
 #[test]
 fn it_makes_delete_request() {
     let rsp = subject().delete("/delete").unwrap();
     assert_strlike_eq(rsp["url"].as_str().unwrap(), http_bin("/delete"));
 }
 
+#[test]
+fn it_makes_delete_request_with_body_and_parameters() {
+    let json = json!({"foo": "bar"});
+    let rsp = subject()
+        .request(Method::Delete, "/delete")
+        .query("key1", "value1")
+        .json(json.clone())
+        .reason("cleanup")
+        .send()
+        .unwrap();
+    assert_strlike_eq(
+        rsp["url"].as_str().unwrap(),
+        http_bin("/delete?key1=value1"),
+    );
+    assert_eq!(rsp["json"], json);
+    assert_strlike_eq(
+        rsp["headers"]["X-Audit-Log-Reason"].as_str().unwrap(),
+        "cleanup",
+    );
+}
+
 #[test]
 fn it_sends_user_agent() {
     let rsp = subject().get("/user-agent").unwrap();
@@ -90,14 +175,199 @@ fn it_sends_auth_header() {
     );
 }
 
+#[test]
+fn it_sends_audit_log_reason() {
+    let rsp = subject()
+        .get_with_options(
+            "/headers",
+            QueryParameters::new(),
+            RequestOptions::new().reason("Spam & abuse: 100%"),
+        )
+        .unwrap();
+    assert_strlike_eq(
+        rsp["headers"]["X-Audit-Log-Reason"].as_str().unwrap(),
+        "Spam%20%26%20abuse%3A%20100%25",
+    );
+}
+
+#[test]
+fn it_sends_custom_headers() {
+    let rsp = subject()
+        .delete_with_options(
+            "/delete",
+            QueryParameters::new(),
+            RequestOptions::new().header("X-Custom", "value"),
+        )
+        .unwrap();
+    assert_strlike_eq(rsp["headers"]["X-Custom"].as_str().unwrap(), "value");
+}
+
 #[test]
 fn it_handles_204_response() {
     let rsp = subject().delete("/status/204").unwrap();
     assert_eq!(rsp, json!({}));
 }
 
+#[test]
+fn it_returns_raw_response() {
+    let rsp = subject()
+        .request(Method::Get, "/response-headers")
+        .query("X-RateLimit-Remaining", "4")
+        .send_raw()
+        .unwrap();
+    assert_eq!(rsp.status(), 200);
+    assert_eq!(rsp.header("x-ratelimit-remaining"), Some("4"));
+    assert_eq!(rsp.json().unwrap()["X-RateLimit-Remaining"], "4");
+}
+
+#[test]
+fn it_returns_raw_non_json_response() {
+    let rsp = subject().request(Method::Get, "/html").send_raw().unwrap();
+    assert!(String::from_utf8_lossy(rsp.body()).contains("<html>"));
+}
+
+#[test]
+fn it_handles_empty_response() {
+    let rsp = subject().request(Method::Head, "/get").send().unwrap();
+    assert_eq!(rsp, serde_json::Value::Null);
+}
+
 #[test]
 fn it_errors_on_404_response() {
     let rsp = subject().get("/status/404");
     assert!(matches!(rsp, Err(Error::HttpError(_))));
 }
+
+#[test]
+fn it_returns_raw_error_response() {
+    let rsp = subject()
+        .request(Method::Get, "/status/404")
+        .send_raw()
+        .unwrap();
+    assert_eq!(rsp.status(), 404);
+    assert!(!rsp.is_success());
+    assert!(matches!(rsp.error_for_status(), Err(Error::HttpError(_))));
+}
+
+#[test]
+fn it_makes_request_with_timeouts() {
+    let rsp = SmallDBuilder::new()
+        .token(DUMMY_TOKEN)
+        .base_url(HTTP_BIN)
+        .connect_timeout(Duration::from_secs(5))
+        .read_timeout(Some(Duration::from_secs(5)))
+        .max_idle_connections(1)
+        .build()
+        .unwrap()
+        .get("/get")
+        .unwrap();
+    assert_strlike_eq(rsp["url"].as_str().unwrap(), http_bin("/get"));
+}
+
+#[test]
+fn it_times_out_slow_response() {
+    let result = SmallDBuilder::new()
+        .token(DUMMY_TOKEN)
+        .base_url(HTTP_BIN)
+        .read_timeout(Some(Duration::from_secs(1)))
+        .build()
+        .unwrap()
+        .get("/delay/3");
+    assert!(matches!(result, Err(Error::HttpError(_))));
+}
+
+#[test]
+fn it_rejects_bad_proxy() {
+    let result = SmallDBuilder::new()
+        .token(DUMMY_TOKEN)
+        .proxy("ftp://proxy.example.com")
+        .build();
+    assert!(matches!(result, Err(Error::ConfigurationError(_))));
+}
+
+#[test]
+fn it_rejects_bad_root_certificate() {
+    let result = SmallDBuilder::new()
+        .token(DUMMY_TOKEN)
+        .root_certificate("not a certificate")
+        .build();
+    assert!(matches!(result, Err(Error::ConfigurationError(_))));
+}
+
+fn webhook_subject() -> Webhook {
+    SmallDBuilder::new()
+        .base_url(http_bin("/anything"))
+        .build_webhook("https://discord.com/api/webhooks/1234/abcd")
+        .unwrap()
+}
+
+#[test]
+fn it_parses_webhook_url() {
+    let webhook = Webhook::from_url("https://discord.com/api/v8/webhooks/1234/abcd").unwrap();
+    assert_eq!(webhook.id(), Snowflake(1234));
+    assert_eq!(webhook.token(), "abcd");
+}
+
+#[test]
+fn it_rejects_bad_webhook_url() {
+    let result = Webhook::from_url("https://discord.com/api/channels/1234");
+    assert!(matches!(result, Err(Error::IllegalArgumentError(_))));
+}
+
+#[test]
+fn it_executes_webhook_without_authorization() {
+    let rsp = webhook_subject()
+        .execute(json!({"content": "hello"}))
+        .wait(true)
+        .thread_id("5678")
+        .send()
+        .unwrap();
+    assert_strlike_eq(
+        rsp["url"].as_str().unwrap(),
+        http_bin("/anything/webhooks/1234/abcd?wait=true&thread_id=5678"),
+    );
+    assert_eq!(rsp["json"], json!({"content": "hello"}));
+    assert!(rsp["headers"].get("Authorization").is_none());
+}
+
+#[test]
+fn it_edits_webhook_message() {
+    let rsp = webhook_subject()
+        .edit_message("@original", json!({"content": "edited"}))
+        .unwrap();
+    assert_strlike_eq(rsp["method"].as_str().unwrap(), "PATCH");
+    assert_strlike_eq(
+        rsp["url"].as_str().unwrap(),
+        http_bin("/anything/webhooks/1234/abcd/messages/@original"),
+    );
+}
+
+#[test]
+fn it_sends_bearer_token() {
+    let rsp = SmallDBuilder::new()
+        .token(DUMMY_TOKEN)
+        .token_type(TokenType::Bearer)
+        .base_url(HTTP_BIN)
+        .build()
+        .unwrap()
+        .get("/headers")
+        .unwrap();
+    assert_strlike_eq(
+        rsp["headers"]["Authorization"].as_str().unwrap(),
+        format!("Bearer {}", DUMMY_TOKEN),
+    );
+}
+
+#[test]
+fn it_makes_form_request() {
+    let rsp = subject()
+        .request(Method::Post, "/post")
+        .form("grant_type", "refresh_token")
+        .form("refresh_token", "a b&c")
+        .send()
+        .unwrap();
+    assert_eq!(
+        rsp["form"],
+        json!({"grant_type": "refresh_token", "refresh_token": "a b&c"})
+    );
+}